@@ -1,18 +1,34 @@
+use std::{collections::HashMap, rc::Rc};
+
 use crate::runtime::Executor;
+use crate::types::Value;
 use crate::Error;
 use crate::{optimizer::default_implementation, parser::Query};
-use crate::{optimizer::Builder, parser::Parser};
+use crate::{
+    optimizer::{Builder, PredicatePushdown},
+    parser::Parser,
+    util::Walker,
+};
 
 pub struct Planner {}
 
 impl Planner {
     pub fn plan(&self, ast: &Query) -> Result<Box<dyn Executor>, Error> {
+        self.plan_with_params(ast, HashMap::new())
+    }
+
+    pub fn plan_with_params(
+        &self,
+        ast: &Query,
+        params: HashMap<String, Value>,
+    ) -> Result<Box<dyn Executor>, Error> {
         let mut builder = Builder::new();
 
         let rel_expr = builder.build(ast)?;
+        let rel_expr = PredicatePushdown::new().walk(rel_expr);
         // println!("{:#?}", rel_expr);
 
-        let exec = default_implementation(&rel_expr)?;
+        let exec = default_implementation(&rel_expr, &Rc::new(params))?;
 
         Ok(exec)
     }
@@ -77,3 +93,53 @@ fn test_selection() {
     }
     exec.close().unwrap();
 }
+
+#[cfg(test)]
+fn execute(query: &str) -> Vec<crate::runtime::ExecutionContext> {
+    let ast = Parser::new().parse(query).unwrap();
+    let mut exec = Planner {}.plan(&ast).unwrap();
+    exec.open().unwrap();
+    let mut res = Vec::new();
+    while let Some(ctx) = exec.next().unwrap() {
+        res.push(ctx);
+    }
+    exec.close().unwrap();
+    res
+}
+
+#[test]
+fn test_update_after_merge() {
+    // MERGE creates the lock table, which is not a label table
+    execute("MERGE (a:Person {name: 'merge_test'}) RETURN a");
+    execute("MATCH (a:Person {name: 'merge_test'}) SET a.name = 'merge_test_2' RETURN a");
+    let res = execute("MATCH (n) WHERE n.name = 'merge_test_2' RETURN n");
+    assert_eq!(res.len(), 1);
+
+    // Property without column is rejected, and the update is rolled back
+    let ast = Parser::new()
+        .parse("MATCH (n) WHERE n.name = 'merge_test_2' SET n.name = 'a', n.no_such_column = 1")
+        .unwrap();
+    let mut exec = Planner {}.plan(&ast).unwrap();
+    let err = exec.open().unwrap_err();
+    assert!(err.msg.contains("no_such_column"));
+    let res = execute("MATCH (n) WHERE n.name = 'merge_test_2' RETURN n");
+    assert_eq!(res.len(), 1);
+    // Removing property without column does nothing
+    execute("MATCH (n) WHERE n.name = 'merge_test_2' REMOVE n.no_such_column");
+    // Updating and deleting null do nothing
+    let optional = "MATCH (a:Person {name: 'merge_test_2'}) \
+        OPTIONAL MATCH (a)-[r:knows]->(b:Person {name: 'no_such_person'})";
+    execute(&format!("{} SET b.name = 'a', r.since = 1 RETURN a", optional));
+    execute(&format!("{} DETACH DELETE b, r", optional));
+    let res = execute("MATCH (n) WHERE n.name = 'merge_test_2' RETURN n");
+    assert_eq!(res.len(), 1);
+    for ctx in execute("MATCH (n) RETURN n").iter() {
+        match ctx.get("n") {
+            Some(Value::Object(node)) => assert!(node.get("id").is_some()),
+            v @ _ => panic!("unexpected node {:?}", v),
+        }
+    }
+    execute("MATCH (a:Person {name: 'merge_test_2'}) DETACH DELETE a");
+    let res = execute("MATCH (n) WHERE n.name = 'merge_test_2' RETURN n");
+    assert!(res.is_empty());
+}
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use nom::bitvec::vec;
 
@@ -6,18 +6,48 @@ use super::expr::*;
 use super::metadata::*;
 use crate::parser::{Expr as ASTExpr, *};
 
-use crate::Error;
+#[cfg(test)]
+use super::pushdown::{variables, PredicatePushdown};
+#[cfg(test)]
+use crate::util::Walker;
+use crate::{
+    runtime::{lookup_function, AggregateFunction},
+    util::{ErrorKind, IdGen},
+    Error,
+};
+
+// Variables of nodes and relationships in a pattern, anonymous ones are
+// binded to hidden variables
+struct PatternNames {
+    nodes: Vec<String>,
+    rels: Vec<String>,
+}
 
 // Builder for logical plans
-pub struct Builder {}
+pub struct Builder {
+    // Label or relationship type of binded variables, which is used to
+    // resolve labels of nodes binded by previous clauses
+    labels: HashMap<String, String>,
+    // Variables binded to relationships
+    relationships: HashSet<String>,
+    // Aggregation function calls collected while building projection items,
+    // aggregation is not allowed elsewhere
+    aggregates: Option<Vec<AggregateCall>>,
+    idgen: IdGen,
+}
 
 impl Builder {
     pub fn new() -> Builder {
-        Builder {}
+        Builder {
+            labels: HashMap::new(),
+            relationships: HashSet::new(),
+            aggregates: None,
+            idgen: IdGen::new(),
+        }
     }
 
     pub fn build(&mut self, ast: &Query) -> Result<RelExpr, Error> {
-        let mut final_expr: RelExpr;
+        let final_expr: RelExpr;
         let mut unions = Vec::new();
         for expr in ast.unions.iter() {
             let u = self.build_union(expr)?;
@@ -25,7 +55,29 @@ impl Builder {
         }
 
         if unions.len() > 1 {
-            unimplemented!();
+            let all = ast.unions[1].all;
+            if ast.unions.iter().skip(1).any(|v| v.all != all) {
+                return Err(Error {
+                    msg: "cannot mix UNION and UNION ALL".to_owned(),
+                    kind: ErrorKind::Semantic,
+                });
+            }
+
+            let columns = Self::union_columns(&ast.unions[0], &unions[0])?;
+            for (i, u) in unions.iter().enumerate().skip(1) {
+                if Self::union_columns(&ast.unions[i], u)? != columns {
+                    return Err(Error {
+                        msg: "all sub queries in an UNION must have the same column names"
+                            .to_owned(),
+                        kind: ErrorKind::Semantic,
+                    });
+                }
+            }
+
+            final_expr = RelExpr::Union(UnionExpr {
+                all: all,
+                children: unions,
+            });
         } else {
             final_expr = unions.pop().unwrap();
         }
@@ -36,11 +88,129 @@ impl Builder {
     pub fn build_scalar(&mut self, expr: &ASTExpr) -> Result<ScalarExpr, Error> {
         let final_expr = match expr {
             ASTExpr::Variable(name) => ScalarExpr::Variable(name.to_owned()),
+            ASTExpr::Parameter(name) => ScalarExpr::Parameter(name.to_owned()),
             ASTExpr::EqualExpr(equal) => {
                 let lhs = self.build_scalar(equal.lhs.as_ref())?;
                 let rhs = self.build_scalar(equal.rhs.as_ref())?;
                 ScalarExpr::Equal(Box::new(lhs), Box::new(rhs))
             }
+            ASTExpr::NotEqualExpr(not_equal) => {
+                let lhs = self.build_scalar(not_equal.lhs.as_ref())?;
+                let rhs = self.build_scalar(not_equal.rhs.as_ref())?;
+                ScalarExpr::NotEqual(Box::new(lhs), Box::new(rhs))
+            }
+            ASTExpr::LessExpr(less) => {
+                let lhs = self.build_scalar(less.lhs.as_ref())?;
+                let rhs = self.build_scalar(less.rhs.as_ref())?;
+                ScalarExpr::Less(Box::new(lhs), Box::new(rhs))
+            }
+            ASTExpr::LessEqualExpr(less_equal) => {
+                let lhs = self.build_scalar(less_equal.lhs.as_ref())?;
+                let rhs = self.build_scalar(less_equal.rhs.as_ref())?;
+                ScalarExpr::LessEqual(Box::new(lhs), Box::new(rhs))
+            }
+            ASTExpr::GreaterExpr(greater) => {
+                let lhs = self.build_scalar(greater.lhs.as_ref())?;
+                let rhs = self.build_scalar(greater.rhs.as_ref())?;
+                ScalarExpr::Greater(Box::new(lhs), Box::new(rhs))
+            }
+            ASTExpr::GreaterEqualExpr(greater_equal) => {
+                let lhs = self.build_scalar(greater_equal.lhs.as_ref())?;
+                let rhs = self.build_scalar(greater_equal.rhs.as_ref())?;
+                ScalarExpr::GreaterEqual(Box::new(lhs), Box::new(rhs))
+            }
+            ASTExpr::AddExpr(add) => {
+                let lhs = self.build_scalar(add.lhs.as_ref())?;
+                let rhs = self.build_scalar(add.rhs.as_ref())?;
+                ScalarExpr::Add(Box::new(lhs), Box::new(rhs))
+            }
+            ASTExpr::SubExpr(sub) => {
+                let lhs = self.build_scalar(sub.lhs.as_ref())?;
+                let rhs = self.build_scalar(sub.rhs.as_ref())?;
+                ScalarExpr::Sub(Box::new(lhs), Box::new(rhs))
+            }
+            ASTExpr::MultExpr(mult) => {
+                let lhs = self.build_scalar(mult.lhs.as_ref())?;
+                let rhs = self.build_scalar(mult.rhs.as_ref())?;
+                ScalarExpr::Mult(Box::new(lhs), Box::new(rhs))
+            }
+            ASTExpr::DivExpr(div) => {
+                let lhs = self.build_scalar(div.lhs.as_ref())?;
+                let rhs = self.build_scalar(div.rhs.as_ref())?;
+                ScalarExpr::Div(Box::new(lhs), Box::new(rhs))
+            }
+            ASTExpr::ModExpr(modulo) => {
+                let lhs = self.build_scalar(modulo.lhs.as_ref())?;
+                let rhs = self.build_scalar(modulo.rhs.as_ref())?;
+                ScalarExpr::Mod(Box::new(lhs), Box::new(rhs))
+            }
+            ASTExpr::PowerExpr(power) => {
+                let lhs = self.build_scalar(power.lhs.as_ref())?;
+                let rhs = self.build_scalar(power.rhs.as_ref())?;
+                ScalarExpr::Power(Box::new(lhs), Box::new(rhs))
+            }
+            ASTExpr::UnarySubExpr(unary_sub) => {
+                ScalarExpr::Negate(Box::new(self.build_scalar(unary_sub.child.as_ref())?))
+            }
+            ASTExpr::StartsWithExpr(starts_with) => {
+                let lhs = self.build_scalar(starts_with.lhs.as_ref())?;
+                let rhs = self.build_scalar(starts_with.rhs.as_ref())?;
+                ScalarExpr::StartsWith(Box::new(lhs), Box::new(rhs))
+            }
+            ASTExpr::EndsWithExpr(ends_with) => {
+                let lhs = self.build_scalar(ends_with.lhs.as_ref())?;
+                let rhs = self.build_scalar(ends_with.rhs.as_ref())?;
+                ScalarExpr::EndsWith(Box::new(lhs), Box::new(rhs))
+            }
+            ASTExpr::ContainsExpr(contains) => {
+                let lhs = self.build_scalar(contains.lhs.as_ref())?;
+                let rhs = self.build_scalar(contains.rhs.as_ref())?;
+                ScalarExpr::Contains(Box::new(lhs), Box::new(rhs))
+            }
+            ASTExpr::RegexMatchExpr(regex_match) => {
+                let lhs = self.build_scalar(regex_match.lhs.as_ref())?;
+                let rhs = self.build_scalar(regex_match.rhs.as_ref())?;
+                ScalarExpr::RegexMatch(Box::new(lhs), Box::new(rhs))
+            }
+            ASTExpr::InExpr(in_expr) => {
+                let lhs = self.build_scalar(in_expr.lhs.as_ref())?;
+                let rhs = self.build_scalar(in_expr.rhs.as_ref())?;
+                ScalarExpr::In(Box::new(lhs), Box::new(rhs))
+            }
+            ASTExpr::FunctionCall(call) => match AggregateFunction::lookup(&call.name) {
+                Some(func) => {
+                    if call.args.len() != func.arity() {
+                        return Err(Error {
+                            msg: format!(
+                                "wrong number of arguments for function `{}`: {}",
+                                call.name,
+                                call.args.len()
+                            ),
+                            kind: ErrorKind::Semantic,
+                        });
+                    }
+                    self.build_aggregate(func, &call.args, call.distinct)?
+                }
+                None => self.build_function_call(call)?,
+            },
+            ASTExpr::CountStar => {
+                self.build_aggregate(AggregateFunction::Count, &Vec::new(), false)?
+            }
+            ASTExpr::CaseExpr(case) => {
+                let operand = match case.operand.as_ref() {
+                    Some(operand) => Some(Box::new(self.build_scalar(operand)?)),
+                    None => None,
+                };
+                let mut alternatives = Vec::new();
+                for (when, then) in case.alternatives.iter() {
+                    alternatives.push((self.build_scalar(when)?, self.build_scalar(then)?));
+                }
+                let default = match case.default.as_ref() {
+                    Some(default) => Some(Box::new(self.build_scalar(default)?)),
+                    None => None,
+                };
+                ScalarExpr::Case(operand, alternatives, default)
+            }
             ASTExpr::PropertyLookup(prop_lookup) => {
                 let child = self.build_scalar(prop_lookup.child.as_ref())?;
                 ScalarExpr::PropertyLookup(Box::new(child), prop_lookup.prop_name.to_owned())
@@ -50,47 +220,258 @@ impl Builder {
                 let rhs = self.build_scalar(and.rhs.as_ref())?;
                 ScalarExpr::LogicAnd(Box::new(lhs), Box::new(rhs))
             }
+            ASTExpr::OrExpr(or) => {
+                let lhs = self.build_scalar(or.lhs.as_ref())?;
+                let rhs = self.build_scalar(or.rhs.as_ref())?;
+                ScalarExpr::LogicOr(Box::new(lhs), Box::new(rhs))
+            }
+            ASTExpr::XorExpr(xor) => {
+                let lhs = self.build_scalar(xor.lhs.as_ref())?;
+                let rhs = self.build_scalar(xor.rhs.as_ref())?;
+                ScalarExpr::LogicXor(Box::new(lhs), Box::new(rhs))
+            }
+            ASTExpr::NotExpr(not) => {
+                ScalarExpr::LogicNot(Box::new(self.build_scalar(not.child.as_ref())?))
+            }
+            ASTExpr::IsNullExpr(is_null) => {
+                ScalarExpr::IsNull(Box::new(self.build_scalar(is_null.child.as_ref())?))
+            }
+            ASTExpr::IsNotNullExpr(is_not_null) => {
+                ScalarExpr::IsNotNull(Box::new(self.build_scalar(is_not_null.child.as_ref())?))
+            }
+            ASTExpr::IntegerLit(v) => ScalarExpr::IntegerLiteral(v.to_owned()),
             ASTExpr::NumberLit(v) => ScalarExpr::NumberLiteral(v.to_owned()),
             ASTExpr::StringLit(v) => ScalarExpr::StringLiteral(v.to_owned()),
-            _ => unimplemented!("{:#?}", expr),
+            ASTExpr::BooleanLit(v) => ScalarExpr::BooleanLiteral(v.to_owned()),
+            ASTExpr::NullLit => ScalarExpr::NullLiteral,
+            ASTExpr::ListLit(elements) => {
+                let mut list = Vec::new();
+                for e in elements.iter() {
+                    list.push(self.build_scalar(e)?);
+                }
+                ScalarExpr::ListLiteral(list)
+            }
+            ASTExpr::MapLit(entries) => {
+                let mut map = Vec::new();
+                for (key, value) in entries.iter() {
+                    map.push((key.to_owned(), self.build_scalar(value)?));
+                }
+                ScalarExpr::MapLiteral(map)
+            }
+            ASTExpr::Subscript(subscript) => {
+                let child = self.build_scalar(subscript.child.as_ref())?;
+                let index = self.build_scalar(subscript.index.as_ref())?;
+                ScalarExpr::Subscript(Box::new(child), Box::new(index))
+            }
+            ASTExpr::MapProjection(projection) => {
+                let child = ScalarExpr::Variable(projection.variable.to_owned());
+                let mut all = false;
+                let mut entries = Vec::new();
+                for item in projection.items.iter() {
+                    match item {
+                        MapProjectionItem::Property(key) => entries.push((
+                            key.to_owned(),
+                            ScalarExpr::PropertyLookup(Box::new(child.clone()), key.to_owned()),
+                        )),
+                        MapProjectionItem::AllProperties => all = true,
+                        MapProjectionItem::Literal(key, value) => {
+                            entries.push((key.to_owned(), self.build_scalar(value)?))
+                        }
+                        MapProjectionItem::Variable(name) => {
+                            entries.push((name.to_owned(), ScalarExpr::Variable(name.to_owned())))
+                        }
+                    }
+                }
+                ScalarExpr::MapProjection(Box::new(child), all, entries)
+            }
+            ASTExpr::Slice(slice) => {
+                let child = self.build_scalar(slice.child.as_ref())?;
+                let from = match slice.from.as_ref() {
+                    Some(from) => Some(Box::new(self.build_scalar(from)?)),
+                    None => None,
+                };
+                let to = match slice.to.as_ref() {
+                    Some(to) => Some(Box::new(self.build_scalar(to)?)),
+                    None => None,
+                };
+                ScalarExpr::Slice(Box::new(child), from, to)
+            }
         };
 
         Ok(final_expr)
     }
 
-    fn build_union(&mut self, union: &Union) -> Result<RelExpr, Error> {
-        let mut final_expr = match union.reading_clause.as_ref().unwrap() {
-            ReadingClause::Match(clause) => self.build_match(clause)?,
-            _ => unimplemented!(),
+    fn build_function_call(&mut self, call: &FunctionCall) -> Result<ScalarExpr, Error> {
+        if call.distinct {
+            return Err(Error {
+                msg: format!("DISTINCT is not allowed in function `{}`", call.name),
+                kind: ErrorKind::Semantic,
+            });
+        }
+        let mut args = Vec::new();
+        for arg in call.args.iter() {
+            args.push(self.build_scalar(arg)?);
+        }
+        let func = lookup_function(&call.name, &args)?;
+        Ok(ScalarExpr::FunctionCall(func, args))
+    }
+
+    // Aggregation function call is replaced with a hidden variable, which is
+    // binded to result of the aggregation
+    fn build_aggregate(
+        &mut self,
+        func: AggregateFunction,
+        args: &Vec<ASTExpr>,
+        distinct: bool,
+    ) -> Result<ScalarExpr, Error> {
+        // Aggregation functions cannot be nested, so arguments are built
+        // without aggregation allowed
+        let mut aggregates = match self.aggregates.take() {
+            Some(aggregates) => aggregates,
+            None => {
+                return Err(Error {
+                    msg: format!("aggregation function `{}` is not allowed here", func.name()),
+                    kind: ErrorKind::Semantic,
+                })
+            }
         };
+        let mut scalar_args = Vec::new();
+        for arg in args.iter() {
+            scalar_args.push(self.build_scalar(arg)?);
+        }
+        let name = self.hidden_variable("agg");
+        aggregates.push(AggregateCall {
+            func: func,
+            args: scalar_args,
+            distinct: distinct,
+            name: name.to_owned(),
+        });
+        self.aggregates = Some(aggregates);
+
+        Ok(ScalarExpr::Variable(name))
+    }
+
+    // Sorted column names of a sub query in UNION
+    fn union_columns(union: &Union, expr: &RelExpr) -> Result<Vec<String>, Error> {
+        if union.return_clause.is_none() {
+            return Err(Error {
+                msg: "sub queries in an UNION must end with RETURN".to_owned(),
+                kind: ErrorKind::Semantic,
+            });
+        }
+        let mut columns = expr.bound_variables();
+        columns.sort();
+        columns.dedup();
+        Ok(columns)
+    }
+
+    fn build_union(&mut self, union: &Union) -> Result<RelExpr, Error> {
+        // Every sub query of UNION has its own scope
+        self.labels.clear();
+        self.relationships.clear();
+
+        let mut input = None;
+        for part in union.parts.iter() {
+            let expr = self.build_clauses(input, &part.reading_clauses, &part.updating_clauses)?;
+            input = Some(self.build_with(expr, &part.with_clause)?);
+        }
 
-        final_expr = self.build_projection(final_expr, &union.return_clause)?;
+        let mut final_expr =
+            self.build_clauses(input, &union.reading_clauses, &union.updating_clauses)?;
+
+        if let Some(return_clause) = union.return_clause.as_ref() {
+            final_expr = self.build_projection_body(
+                final_expr,
+                return_clause.star,
+                &return_clause.projections,
+                &return_clause.sort_items,
+                &return_clause.skip,
+                &return_clause.limit,
+            )?;
+        }
 
         Ok(final_expr)
     }
 
-    fn build_match(&mut self, match_clause: &MatchClause) -> Result<RelExpr, Error> {
-        let mut exprs = Vec::new();
-        for p in match_clause.pattern.iter() {
-            exprs.push(self.build_pattern(p)?);
+    // Build clauses of a query part upon the plan of previous parts
+    fn build_clauses(
+        &mut self,
+        input: Option<RelExpr>,
+        reading_clauses: &Vec<ReadingClause>,
+        updating_clauses: &Vec<UpdatingClause>,
+    ) -> Result<RelExpr, Error> {
+        let mut input = input;
+        for clause in reading_clauses.iter() {
+            input = match clause {
+                ReadingClause::Match(clause) => Some(self.build_match(input, clause)?),
+                ReadingClause::Unwind(clause) => Some(self.build_unwind(input, clause)?),
+            };
         }
+        let mut final_expr = input.unwrap_or(RelExpr::SingleRow);
 
-        // If there are more than one patterns, join them
-        let mut final_expr = if exprs.len() > 1 {
-            exprs
-                .into_iter()
-                .map(|v| Result::<RelExpr, Error>::Ok(v))
-                .fold_first(|acc, expr| self.build_join(acc.unwrap(), expr.unwrap()))
-                .unwrap()?
-        } else {
-            exprs.pop().unwrap()
-        };
+        for clause in updating_clauses.iter() {
+            final_expr = match clause {
+                UpdatingClause::Create(create) => self.build_create(final_expr, create)?,
+                UpdatingClause::Merge(merge) => self.build_merge(final_expr, merge)?,
+                UpdatingClause::Delete(delete) => self.build_delete(final_expr, delete)?,
+                UpdatingClause::Set(set) => {
+                    let items = self.build_update_items(&set.items)?;
+                    self.build_update(final_expr, items)?
+                }
+                UpdatingClause::Remove(remove) => {
+                    let items = self.build_remove_items(&remove.items)?;
+                    self.build_update(final_expr, items)?
+                }
+            };
+        }
+
+        Ok(final_expr)
+    }
+
+    fn build_with(&mut self, expr: RelExpr, with_clause: &WithClause) -> Result<RelExpr, Error> {
+        let mut final_expr = self.build_projection_body(
+            expr,
+            with_clause.star,
+            &with_clause.projections,
+            &with_clause.sort_items,
+            &with_clause.skip,
+            &with_clause.limit,
+        )?;
 
-        final_expr = if !match_clause.filter.is_empty() {
+        if !with_clause.filter.is_empty() {
             let mut scalar = Vec::new();
-            for predicate in match_clause.filter.iter() {
+            for predicate in with_clause.filter.iter() {
                 scalar.push(self.build_scalar(&predicate)?);
             }
+            final_expr = self.build_selection(final_expr, &scalar)?;
+        }
+
+        Ok(final_expr)
+    }
+
+    // Patterns are matched upon input, nodes binded by input are not scanned again
+    fn build_match(
+        &mut self,
+        input: Option<RelExpr>,
+        match_clause: &MatchClause,
+    ) -> Result<RelExpr, Error> {
+        if match_clause.optional {
+            return self.build_optional_match(input, match_clause);
+        }
+
+        let mut final_expr = input;
+        let mut scalar = Vec::new();
+        for p in match_clause.pattern.iter() {
+            let names = self.name_pattern(p);
+            final_expr = Some(self.build_pattern(final_expr, p, &names, &mut scalar)?);
+        }
+        let mut final_expr = final_expr.unwrap();
+
+        for predicate in match_clause.filter.iter() {
+            scalar.push(self.build_scalar(&predicate)?);
+        }
+
+        final_expr = if !scalar.is_empty() {
             self.build_selection(final_expr, &scalar)?
         } else {
             final_expr
@@ -99,6 +480,59 @@ impl Builder {
         Ok(final_expr)
     }
 
+    // Pattern of OPTIONAL MATCH is planned independently, and correlated with
+    // input by the variables they share
+    fn build_optional_match(
+        &mut self,
+        input: Option<RelExpr>,
+        match_clause: &MatchClause,
+    ) -> Result<RelExpr, Error> {
+        let input = input.unwrap_or(RelExpr::SingleRow);
+        let mut pattern = None;
+        let mut filter = Vec::new();
+        for p in match_clause.pattern.iter() {
+            let names = self.name_pattern(p);
+            pattern = Some(self.build_pattern(pattern, p, &names, &mut filter)?);
+        }
+        let pattern = pattern.unwrap();
+
+        for predicate in match_clause.filter.iter() {
+            filter.push(self.build_scalar(&predicate)?);
+        }
+
+        let bound = input.bound_variables();
+        let mut optional_names = Vec::new();
+        for name in pattern.bound_variables().into_iter() {
+            if !bound.contains(&name) && !optional_names.contains(&name) {
+                optional_names.push(name);
+            }
+        }
+
+        Ok(RelExpr::LeftOuterApply(LeftOuterApplyExpr {
+            pattern: Box::new(pattern),
+            filter: filter,
+            optional_names: optional_names,
+            child: Box::new(input),
+        }))
+    }
+
+    fn build_unwind(
+        &mut self,
+        input: Option<RelExpr>,
+        unwind_clause: &UnwindClause,
+    ) -> Result<RelExpr, Error> {
+        let expr = self.build_scalar(&unwind_clause.expr)?;
+        // Elements of list are not entities in label tables
+        self.labels.remove(&unwind_clause.name);
+        self.relationships.remove(&unwind_clause.name);
+
+        Ok(RelExpr::Unwind(UnwindExpr {
+            expr: expr,
+            name: unwind_clause.name.to_owned(),
+            child: Box::new(input.unwrap_or(RelExpr::SingleRow)),
+        }))
+    }
+
     fn build_selection(
         &mut self,
         expr: RelExpr,
@@ -112,28 +546,246 @@ impl Builder {
         Ok(RelExpr::Selection(final_expr))
     }
 
+    // Build projection with ORDER BY, SKIP and LIMIT
+    fn build_projection_body(
+        &mut self,
+        expr: RelExpr,
+        star: bool,
+        projections: &Vec<(ASTExpr, String)>,
+        sort_items: &Vec<SortItem>,
+        skip: &Option<ASTExpr>,
+        limit: &Option<ASTExpr>,
+    ) -> Result<RelExpr, Error> {
+        let skip = match skip {
+            Some(skip) => Some(self.build_scalar(skip)?),
+            None => None,
+        };
+        let limit = match limit {
+            Some(limit) => Some(self.build_scalar(limit)?),
+            None => None,
+        };
+
+        let mut columns = if star {
+            visible_variables(&expr)
+        } else {
+            Vec::new()
+        };
+        columns.extend(projections.iter().map(|v| v.1.to_owned()));
+
+        if sort_items.is_empty() {
+            let final_expr = self.build_projection(expr, star, false, projections)?;
+            return self.build_limit(final_expr, skip, limit);
+        }
+
+        // Sort keys can refer to both projected aliases and variables in scope,
+        // so variables are kept until rows are sorted.
+        let mut final_expr = self.build_projection(expr, star, true, projections)?;
+        let mut keys = Vec::new();
+        for item in sort_items.iter() {
+            keys.push(SortKey {
+                expr: self.build_scalar(&item.expr)?,
+                descending: matches!(item.ordering, SortOrdering::Descending),
+            });
+        }
+        final_expr = match limit {
+            Some(limit) => RelExpr::TopN(TopNExpr {
+                keys: keys,
+                skip: skip,
+                limit: limit,
+                child: Box::new(final_expr),
+            }),
+            None => self.build_limit(
+                RelExpr::Sort(SortExpr {
+                    keys: keys,
+                    child: Box::new(final_expr),
+                }),
+                skip,
+                None,
+            )?,
+        };
+
+        let projects = columns
+            .into_iter()
+            .map(|v| (ScalarExpr::Variable(v.to_owned()), v))
+            .collect();
+        final_expr = RelExpr::Projection(ProjectExpr {
+            projects: projects,
+            star: false,
+            child: Box::new(final_expr),
+        });
+
+        Ok(final_expr)
+    }
+
+    fn build_limit(
+        &mut self,
+        expr: RelExpr,
+        skip: Option<ScalarExpr>,
+        limit: Option<ScalarExpr>,
+    ) -> Result<RelExpr, Error> {
+        if skip.is_none() && limit.is_none() {
+            return Ok(expr);
+        }
+
+        Ok(RelExpr::Limit(LimitExpr {
+            skip: skip,
+            limit: limit,
+            child: Box::new(expr),
+        }))
+    }
+
+    // Projection items with aggregation functions are aggregated, and the other
+    // items become grouping keys. Variables referred outside of aggregation
+    // functions in aggregated items are grouping keys as well. `*` projects
+    // variables visible to users, and all variables in scope are kept if
+    // `keep` is set and there is no aggregation.
     fn build_projection(
         &mut self,
         expr: RelExpr,
-        return_clause: &ReturnClause,
+        star: bool,
+        keep: bool,
+        projections: &Vec<(ASTExpr, String)>,
     ) -> Result<RelExpr, Error> {
         let mut projects = Vec::new();
-        for (p, alias) in return_clause.projections.iter() {
-            let project = self.build_scalar(p)?;
+        let mut aggregated = Vec::new();
+        if star {
+            for name in visible_variables(&expr).into_iter() {
+                projects.push((ScalarExpr::Variable(name.to_owned()), name));
+                aggregated.push(false);
+            }
+        }
+        self.aggregates = Some(Vec::new());
+        for (p, alias) in projections.iter() {
+            let count = self.aggregates.as_ref().map_or(0, |v| v.len());
+            let project = match self.build_scalar(p) {
+                Ok(project) => project,
+                Err(e) => {
+                    self.aggregates = None;
+                    return Err(e);
+                }
+            };
+            aggregated.push(self.aggregates.as_ref().map_or(0, |v| v.len()) > count);
+            // Aliased variables are still the same entities
+            if let ScalarExpr::Variable(name) = &project {
+                if let Some(label) = self.labels.get(name).cloned() {
+                    self.labels.insert(alias.to_owned(), label);
+                }
+                if self.relationships.contains(name) {
+                    self.relationships.insert(alias.to_owned());
+                }
+            }
             projects.push((project, alias.to_owned()));
         }
+        let aggregates = self.aggregates.take().unwrap_or_default();
+
+        if aggregates.is_empty() {
+            let final_expr = ProjectExpr {
+                projects: projects,
+                star: keep,
+
+                child: Box::new(expr),
+            };
+            return Ok(RelExpr::Projection(final_expr));
+        }
 
+        // Grouping keys are binded to their aliases, so they are projected as
+        // variables after aggregation
+        let mut keys: Vec<(ScalarExpr, String)> = Vec::new();
+        let mut items = Vec::new();
+        let mut grouped_variables = Vec::new();
+        for ((project, alias), aggregated) in projects.into_iter().zip(aggregated.into_iter()) {
+            if aggregated {
+                for name in variables(&project).into_iter() {
+                    if !aggregates.iter().any(|v| v.name == name)
+                        && !grouped_variables.contains(&name)
+                    {
+                        grouped_variables.push(name);
+                    }
+                }
+                items.push((project, alias));
+            } else {
+                items.push((ScalarExpr::Variable(alias.to_owned()), alias.to_owned()));
+                keys.push((project, alias));
+            }
+        }
+        for name in grouped_variables.into_iter() {
+            match keys.iter().find(|v| v.1 == name) {
+                Some((ScalarExpr::Variable(key), _)) if key == &name => {}
+                Some(_) => {
+                    return Err(Error {
+                        msg: format!(
+                            "variable `{}` in aggregation is shadowed by a grouping key",
+                            name
+                        ),
+                        kind: ErrorKind::Semantic,
+                    })
+                }
+                None => keys.push((ScalarExpr::Variable(name.to_owned()), name)),
+            }
+        }
         let final_expr = ProjectExpr {
-            projects: projects,
-            star: return_clause.star,
+            projects: items,
+            star: false,
 
-            child: Box::new(expr),
+            child: Box::new(RelExpr::Aggregate(AggregateExpr {
+                group_by: keys,
+                aggregates: aggregates,
+                child: Box::new(expr),
+            })),
         };
 
         Ok(RelExpr::Projection(final_expr))
     }
 
-    fn build_pattern(&mut self, pattern: &Pattern) -> Result<RelExpr, Error> {
+    // Hidden variables are generated for anonymous entities and intermediate
+    // results, they are not visible to users
+    fn hidden_variable(&mut self, prefix: &str) -> String {
+        format!("__{}{}", prefix, self.idgen.next())
+    }
+
+    fn name_pattern(&mut self, pattern: &Pattern) -> PatternNames {
+        let mut names = PatternNames {
+            nodes: Vec::new(),
+            rels: Vec::new(),
+        };
+        for node in pattern.nodes.iter() {
+            let name = match node.name.as_ref() {
+                Some(name) => name.to_owned(),
+                None => self.hidden_variable("anon"),
+            };
+            names.nodes.push(name);
+        }
+        for rel in pattern.rels.iter() {
+            let name = match rel.name.as_ref() {
+                Some(name) => name.to_owned(),
+                None => self.hidden_variable("anon"),
+            };
+            names.rels.push(name);
+        }
+
+        names
+    }
+
+    // Build plan of pattern upon input, predicates of inline properties are
+    // appended to `predicates`
+    fn build_pattern(
+        &mut self,
+        input: Option<RelExpr>,
+        pattern: &Pattern,
+        names: &PatternNames,
+        predicates: &mut Vec<ScalarExpr>,
+    ) -> Result<RelExpr, Error> {
+        for (rel, name) in pattern.rels.iter().zip(names.rels.iter()) {
+            // Properties of variable length relationship are checked on every
+            // relationship while expanding
+            if rel.length.is_none() {
+                predicates.extend(self.build_property_predicates(name, &rel.properties)?);
+            }
+        }
+        for (node, name) in pattern.nodes.iter().zip(names.nodes.iter()) {
+            predicates.extend(self.build_property_predicates(name, &node.properties)?);
+        }
+
         // graph is an adjacent list, which stores topology of query graph pattern.
         // Elements of graph indicate index of NodePattern in pattern.nodes
         let mut graph = Vec::<Vec<usize>>::new();
@@ -142,7 +794,8 @@ impl Builder {
             let r = pattern.rels.get(i).unwrap();
             match r.direction {
                 Direction::Left => graph.get_mut(i + 1).unwrap().push(i),
-                Direction::Right => graph.get_mut(i).unwrap().push(i + 1),
+                // Undirected relationships are expanded from left to right
+                Direction::Right | Direction::Both => graph.get_mut(i).unwrap().push(i + 1),
             };
         }
 
@@ -187,17 +840,25 @@ impl Builder {
             })
         }
 
-        let mut final_expr: RelExpr;
-
-        // Build paths
-        let mut exprs = Vec::<RelExpr>::new();
+        // Build paths, paths start from binded nodes are expanded from input directly
+        let mut final_expr = input;
         for path in paths.iter() {
-            let nodes: Vec<&NodePattern> = path
-                .into_iter()
-                .map(|v| pattern.nodes.get(v.to_owned()).unwrap())
-                .collect();
-            let mut expr = self.build_scan(nodes.get(0).unwrap())?;
-            for i in 1..nodes.len() {
+            let start = path.get(0).unwrap().to_owned();
+            let bound = match final_expr.as_ref() {
+                Some(expr) => expr.bound_variables(),
+                None => Vec::new(),
+            };
+            let mut expr = if bound.contains(&names.nodes[start]) {
+                let expr = final_expr.take().unwrap();
+                self.check_bound_node_labels(expr, &pattern.nodes[start], &names.nodes[start])?
+            } else {
+                let scan = self.build_scan(&pattern.nodes[start], &names.nodes[start])?;
+                match final_expr.take() {
+                    Some(expr) => self.build_join(expr, scan)?,
+                    None => scan,
+                }
+            };
+            for i in 1..path.len() {
                 let start = path.get(i - 1).unwrap().to_owned();
                 let end = path.get(i).unwrap().to_owned();
                 assert!((start as i64 - end as i64).abs() == 1);
@@ -208,35 +869,85 @@ impl Builder {
 
                 expr = self.build_expand(
                     expr,
-                    pattern.nodes.get(start).unwrap(),
+                    &names.nodes[start],
                     pattern.nodes.get(end).unwrap(),
+                    &names.nodes[end],
                     pattern.rels.get(rel).unwrap(),
+                    &names.rels[rel],
                 )?;
             }
 
-            exprs.push(expr);
+            final_expr = Some(expr);
         }
 
-        // Join paths
-        final_expr = if exprs.len() > 1 {
-            exprs
-                .into_iter()
-                .map(|v| Result::<RelExpr, Error>::Ok(v))
-                .fold_first(|acc, expr| self.build_join(acc.unwrap(), expr.unwrap()))
-                .unwrap()?
-        } else {
-            exprs.pop().unwrap()
-        };
+        Ok(final_expr.unwrap())
+    }
 
-        Ok(final_expr)
+    fn build_property_predicates(
+        &mut self,
+        name: &String,
+        properties: &Vec<(String, ASTExpr)>,
+    ) -> Result<Vec<ScalarExpr>, Error> {
+        let mut predicates = Vec::new();
+        for (key, value) in properties.iter() {
+            let prop = ScalarExpr::PropertyLookup(
+                Box::new(ScalarExpr::Variable(name.to_owned())),
+                key.to_owned(),
+            );
+            let value = self.build_scalar(value)?;
+            predicates.push(ScalarExpr::Equal(Box::new(prop), Box::new(value)));
+        }
+
+        Ok(predicates)
+    }
+
+    // Resolve labels of node pattern, labels of node without label could be
+    // declared by previous clauses. Node of any label is matched if no label
+    // is resolved.
+    fn resolve_node_labels(&self, node_pattern: &NodePattern, name: &String) -> Vec<String> {
+        if !node_pattern.labels.is_empty() {
+            return node_pattern.labels.to_owned();
+        }
+        match self.labels.get(name) {
+            Some(label) => vec![label.to_owned()],
+            None => Vec::new(),
+        }
+    }
+
+    // Label of node is recorded, so that patterns of later clauses binding
+    // the node again know its label
+    fn record_node_labels(&mut self, name: &String, labels: &Vec<String>) {
+        if let Some(label) = labels.get(0) {
+            self.labels.insert(name.to_owned(), label.to_owned());
+        }
     }
 
-    fn build_scan(&mut self, node_pattern: &NodePattern) -> Result<RelExpr, Error> {
+    // Labels of bound node declared again by a later pattern are checked by
+    // joining it with nodes of the labels, which is skipped if the node is
+    // known to have the labels
+    fn check_bound_node_labels(
+        &mut self,
+        expr: RelExpr,
+        node_pattern: &NodePattern,
+        name: &String,
+    ) -> Result<RelExpr, Error> {
+        let known = self.labels.get(name);
+        if node_pattern.labels.iter().all(|v| Some(v) == known) {
+            return Ok(expr);
+        }
+        let scan = self.build_scan(node_pattern, name)?;
+        self.build_join(expr, scan)
+    }
+
+    fn build_scan(&mut self, node_pattern: &NodePattern, name: &String) -> Result<RelExpr, Error> {
+        let labels = self.resolve_node_labels(node_pattern, name);
         let scan = ScanExpr {
-            binded_name: node_pattern.name.to_owned(),
-            all: node_pattern.labels.len() == 0,
-            label: node_pattern.labels.get(0).unwrap().to_owned(),
+            binded_name: name.to_owned(),
+            all: labels.is_empty(),
+            labels: labels,
+            filter: Vec::new(),
         };
+        self.record_node_labels(&scan.binded_name, &scan.labels);
 
         Ok(RelExpr::NodeScan(scan))
     }
@@ -244,46 +955,1127 @@ impl Builder {
     fn build_expand(
         &mut self,
         expr: RelExpr,
-        start_node: &NodePattern,
+        start_name: &String,
         end_node: &NodePattern,
+        end_name: &String,
         rel: &RelationshipPattern,
+        rel_name: &String,
     ) -> Result<RelExpr, Error> {
+        if let Some(length) = rel.length.as_ref() {
+            return self.build_var_length_expand(
+                expr, start_name, end_node, end_name, rel, rel_name, length,
+            );
+        }
         let expand = ExpandExpr {
-            start_name: start_node.name.to_owned(),
-            end_name: end_node.name.to_owned(),
-            rel_name: rel.name.to_owned(),
-            rel_type: rel.types.get(0).unwrap().to_owned(),
-            end_label: end_node.labels.get(0).unwrap().to_owned(),
+            start_name: start_name.to_owned(),
+            end_name: end_name.to_owned(),
+            rel_name: rel_name.to_owned(),
+            rel_types: rel.types.to_owned(),
+            end_labels: self.resolve_node_labels(end_node, end_name),
+            undirected: matches!(rel.direction, Direction::Both),
+            rel_filter: Vec::new(),
+            end_filter: Vec::new(),
             child: Box::new(expr),
         };
+        // Table of relationship to be updated is known only if it has exactly
+        // one type
+        if let [rel_type] = expand.rel_types.as_slice() {
+            self.labels
+                .insert(expand.rel_name.to_owned(), rel_type.to_owned());
+        }
+        self.relationships.insert(expand.rel_name.to_owned());
+        self.record_node_labels(&expand.end_name, &expand.end_labels);
 
         Ok(RelExpr::Expand(expand))
     }
 
-    fn build_join(&mut self, lhs: RelExpr, rhs: RelExpr) -> Result<RelExpr, Error> {
-        let join = JoinExpr {
-            join_type: JoinType::CartesianProduct,
-            lhs: Box::new(lhs),
-            rhs: Box::new(rhs),
+    fn build_var_length_expand(
+        &mut self,
+        expr: RelExpr,
+        start_name: &String,
+        end_node: &NodePattern,
+        end_name: &String,
+        rel: &RelationshipPattern,
+        rel_name: &String,
+        length: &PathLength,
+    ) -> Result<RelExpr, Error> {
+        let min_hops = length.min.unwrap_or(1);
+        if let Some(max_hops) = length.max {
+            if max_hops < min_hops {
+                return Err(Error {
+                    msg: format!("invalid range of hops: {}..{}", min_hops, max_hops),
+                    kind: ErrorKind::Semantic,
+                });
+            }
+        }
+        let expand = VarLengthExpandExpr {
+            start_name: start_name.to_owned(),
+            end_name: end_name.to_owned(),
+            rel_name: rel_name.to_owned(),
+            rel_types: rel.types.to_owned(),
+            end_labels: self.resolve_node_labels(end_node, end_name),
+            min_hops: min_hops,
+            max_hops: length.max,
+            undirected: matches!(rel.direction, Direction::Both),
+            rel_filter: self.build_property_predicates(rel_name, &rel.properties)?,
+            end_filter: Vec::new(),
+            child: Box::new(expr),
         };
+        // Relationship variable is binded to a list, so it's not recorded as
+        // a relationship
+        self.record_node_labels(&expand.end_name, &expand.end_labels);
 
-        Ok(RelExpr::Join(join))
+        Ok(RelExpr::VarLengthExpand(expand))
     }
-}
 
-#[test]
-fn test_builder() {
-    let parser = Parser {};
-    let ast = parser
-        .parse(
-            r#"
-    MATCH (a:Label1)-[r:Type1]->(b:Label1)<-[r1:Type1]-(c:Label2)
-    RETURN a.a, b, c AS d;
-    "#,
-        )
-        .unwrap();
+    fn build_create(&mut self, expr: RelExpr, clause: &CreateClause) -> Result<RelExpr, Error> {
+        let mut bound: HashSet<String> = expr.bound_variables().into_iter().collect();
+        let mut nodes = Vec::new();
+        let mut rels = Vec::new();
+        for pattern in clause.pattern.iter() {
+            if pattern
+                .rels
+                .iter()
+                .any(|v| matches!(v.direction, Direction::Both))
+            {
+                return Err(Error {
+                    msg: String::from("only directed relationships can be created"),
+                    kind: ErrorKind::Semantic,
+                });
+            }
+            let names = self.name_pattern(pattern);
+            self.build_create_pattern(pattern, &names, &mut bound, &mut nodes, &mut rels)?;
+        }
 
-    let mut builder = Builder::new();
+        Ok(RelExpr::Create(CreateExpr {
+            nodes: nodes,
+            rels: rels,
+            child: Box::new(expr),
+        }))
+    }
 
-    // println!("{:#?}", builder.build(&ast).unwrap());
+    // Resolve entities to be created in pattern, nodes in `bound` are referenced
+    // instead of being created.
+    fn build_create_pattern(
+        &mut self,
+        pattern: &Pattern,
+        names: &PatternNames,
+        bound: &mut HashSet<String>,
+        nodes: &mut Vec<CreateNode>,
+        rels: &mut Vec<CreateRel>,
+    ) -> Result<(), Error> {
+        for (node, name) in pattern.nodes.iter().zip(names.nodes.iter()) {
+            if bound.contains(name) {
+                // Reference to an existing node, which cannot be redeclared
+                if !node.labels.is_empty() || !node.properties.is_empty() {
+                    return Err(Error {
+                        msg: format!("variable `{}` already declared", name),
+                        kind: ErrorKind::Semantic,
+                    });
+                }
+                continue;
+            }
+            if node.labels.len() != 1 {
+                return Err(Error {
+                    msg: format!(
+                        "exactly one label is required to create node `{}`",
+                        node.name.as_deref().unwrap_or("")
+                    ),
+                    kind: ErrorKind::Semantic,
+                });
+            }
+            let mut properties = Vec::new();
+            for (key, value) in node.properties.iter() {
+                properties.push((key.to_owned(), self.build_scalar(value)?));
+            }
+            let label = node.labels.get(0).unwrap().to_owned();
+            self.labels.insert(name.to_owned(), label.to_owned());
+            nodes.push(CreateNode {
+                name: name.to_owned(),
+                label: label,
+                properties: properties,
+            });
+            bound.insert(name.to_owned());
+        }
+
+        for i in 0..pattern.rels.len() {
+            let rel = pattern.rels.get(i).unwrap();
+            if rel.length.is_some() {
+                return Err(Error {
+                    msg: String::from("variable length relationship cannot be created"),
+                    kind: ErrorKind::Semantic,
+                });
+            }
+            if rel.types.len() != 1 {
+                return Err(Error {
+                    msg: String::from("exactly one type is required to create relationship"),
+                    kind: ErrorKind::Semantic,
+                });
+            }
+            let rel_type = rel.types.get(0).unwrap().to_owned();
+            if let Some(name) = rel.name.as_ref() {
+                if !bound.insert(name.to_owned()) {
+                    return Err(Error {
+                        msg: format!("variable `{}` already declared", name),
+                        kind: ErrorKind::Semantic,
+                    });
+                }
+                self.labels.insert(name.to_owned(), rel_type.to_owned());
+                self.relationships.insert(name.to_owned());
+            }
+            // Relationships of undirected pattern in MERGE are created from
+            // left to right
+            let (start, end) = match rel.direction {
+                Direction::Left => (i + 1, i),
+                Direction::Right | Direction::Both => (i, i + 1),
+            };
+            let mut properties = Vec::new();
+            for (key, value) in rel.properties.iter() {
+                properties.push((key.to_owned(), self.build_scalar(value)?));
+            }
+            rels.push(CreateRel {
+                name: rel.name.to_owned(),
+                rel_type: rel_type,
+                start_name: names.nodes[start].to_owned(),
+                end_name: names.nodes[end].to_owned(),
+                properties: properties,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn build_merge(&mut self, expr: RelExpr, clause: &MergeClause) -> Result<RelExpr, Error> {
+        let mut bound: HashSet<String> = expr.bound_variables().into_iter().collect();
+        let mut filter = Vec::new();
+        // Anonymous nodes are binded to the same hidden variables when matched
+        // and created
+        let names = self.name_pattern(&clause.pattern);
+        let pattern = self.build_pattern(None, &clause.pattern, &names, &mut filter)?;
+        let mut nodes = Vec::new();
+        let mut rels = Vec::new();
+        self.build_create_pattern(&clause.pattern, &names, &mut bound, &mut nodes, &mut rels)?;
+
+        let on_match = self.build_update_items(&clause.on_match)?;
+        let on_create = self.build_update_items(&clause.on_create)?;
+
+        Ok(RelExpr::Merge(MergeExpr {
+            pattern: Box::new(pattern),
+            filter: filter,
+            nodes: nodes,
+            rels: rels,
+            on_match: on_match,
+            on_create: on_create,
+            child: Box::new(expr),
+        }))
+    }
+
+    fn build_delete(&mut self, expr: RelExpr, clause: &DeleteClause) -> Result<RelExpr, Error> {
+        let mut items = Vec::new();
+        for e in clause.exprs.iter() {
+            match e {
+                ASTExpr::Variable(name) => items.push(DeleteItem {
+                    name: name.to_owned(),
+                    relationship: self.relationships.contains(name),
+                }),
+                _ => {
+                    return Err(Error {
+                        msg: format!("cannot delete `{}`, which is not a variable", e),
+                        kind: ErrorKind::Semantic,
+                    })
+                }
+            }
+        }
+
+        Ok(RelExpr::Delete(DeleteExpr {
+            items: items,
+            detach: clause.detach,
+            child: Box::new(expr),
+        }))
+    }
+
+    fn build_update(&mut self, expr: RelExpr, items: Vec<UpdateItem>) -> Result<RelExpr, Error> {
+        Ok(RelExpr::Update(UpdateExpr {
+            items: items,
+            child: Box::new(expr),
+        }))
+    }
+
+    fn build_update_items(&mut self, items: &Vec<SetItem>) -> Result<Vec<UpdateItem>, Error> {
+        let mut result = Vec::new();
+        for item in items.iter() {
+            match item {
+                SetItem::Property {
+                    variable,
+                    key,
+                    value,
+                } => result.push(UpdateItem::Property {
+                    name: variable.to_owned(),
+                    key: key.to_owned(),
+                    value: self.build_scalar(value)?,
+                }),
+                SetItem::Replace {
+                    variable,
+                    properties,
+                }
+                | SetItem::Append {
+                    variable,
+                    properties,
+                } => {
+                    let mut props = Vec::new();
+                    for (key, value) in properties.iter() {
+                        props.push((key.to_owned(), self.build_scalar(value)?));
+                    }
+                    result.push(UpdateItem::Properties {
+                        name: variable.to_owned(),
+                        properties: props,
+                        replace: matches!(item, SetItem::Replace { .. }),
+                    });
+                }
+                SetItem::Labels { variable, labels } => result.push(UpdateItem::AddLabels {
+                    name: variable.to_owned(),
+                    labels: labels.to_owned(),
+                }),
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn build_remove_items(&mut self, items: &Vec<RemoveItem>) -> Result<Vec<UpdateItem>, Error> {
+        let mut result = Vec::new();
+        for item in items.iter() {
+            match item {
+                RemoveItem::Property { variable, key } => result.push(UpdateItem::RemoveProperty {
+                    name: variable.to_owned(),
+                    key: key.to_owned(),
+                }),
+                RemoveItem::Labels { variable, labels } => result.push(UpdateItem::RemoveLabels {
+                    name: variable.to_owned(),
+                    labels: labels.to_owned(),
+                }),
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn build_join(&mut self, lhs: RelExpr, rhs: RelExpr) -> Result<RelExpr, Error> {
+        let join = JoinExpr {
+            join_type: JoinType::CartesianProduct,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        };
+
+        Ok(RelExpr::Join(join))
+    }
+}
+
+// Variables in scope which are not hidden
+fn visible_variables(expr: &RelExpr) -> Vec<String> {
+    let mut names = Vec::new();
+    for name in expr.bound_variables().into_iter() {
+        if !name.starts_with("__") && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+#[test]
+fn test_builder() {
+    let parser = Parser {};
+    let ast = parser
+        .parse(
+            r#"
+    MATCH (a:Label1)-[r:Type1]->(b:Label1)<-[r1:Type1]-(c:Label2)
+    RETURN a.a, b, c AS d;
+    "#,
+        )
+        .unwrap();
+
+    let mut builder = Builder::new();
+
+    // println!("{:#?}", builder.build(&ast).unwrap());
+}
+
+#[test]
+fn test_build_create() {
+    let parser = Parser {};
+    let ast = parser
+        .parse(
+            r#"
+    CREATE (n:Person {name: 'A'})-[:knows {since: 2020}]->(m:Person)
+    "#,
+        )
+        .unwrap();
+
+    let mut builder = Builder::new();
+    match builder.build(&ast).unwrap() {
+        RelExpr::Create(create) => {
+            assert_eq!(create.nodes.len(), 2);
+            assert_eq!(create.rels.len(), 1);
+        }
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
+}
+
+#[test]
+fn test_build_merge() {
+    let parser = Parser {};
+    let ast = parser
+        .parse(
+            r#"
+    MATCH (a:Person)
+    MERGE (a)-[:knows]->(b:Person {name: 'B'})
+    ON MATCH SET b.visits = 1
+    RETURN a, b;
+    "#,
+        )
+        .unwrap();
+
+    let mut builder = Builder::new();
+    match builder.build(&ast).unwrap() {
+        RelExpr::Projection(project) => match *project.child {
+            RelExpr::Merge(merge) => {
+                assert_eq!(merge.nodes.len(), 1);
+                assert_eq!(merge.rels.len(), 1);
+                assert_eq!(merge.filter.len(), 1);
+                assert_eq!(merge.on_match.len(), 1);
+            }
+            expr @ _ => panic!("unexpected plan {:#?}", expr),
+        },
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
+}
+
+#[test]
+fn test_build_set() {
+    let parser = Parser {};
+    let ast = parser
+        .parse(
+            r#"
+    MATCH (a:Person)-[r:knows]->(b:Person)
+    SET r.since = 2020, b = {name: 'B'}
+    REMOVE a.email
+    "#,
+        )
+        .unwrap();
+
+    let mut builder = Builder::new();
+    match builder.build(&ast).unwrap() {
+        RelExpr::Update(remove) => match *remove.child {
+            RelExpr::Update(set) => {
+                assert_eq!(set.items.len(), 2);
+            }
+            expr @ _ => panic!("unexpected plan {:#?}", expr),
+        },
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
+}
+
+#[test]
+fn test_build_delete() {
+    let parser = Parser {};
+    let ast = parser
+        .parse(
+            r#"
+    MATCH (a:Person)-[r:knows]->(b:Person)
+    DETACH DELETE r, b
+    "#,
+        )
+        .unwrap();
+
+    let mut builder = Builder::new();
+    match builder.build(&ast).unwrap() {
+        RelExpr::Delete(delete) => {
+            assert!(delete.detach);
+            assert!(delete.items.get(0).unwrap().relationship);
+            assert!(!delete.items.get(1).unwrap().relationship);
+        }
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
+
+    // Tables of deleted entities are resolved when deleting them
+    for query in [
+        "MATCH (n) DETACH DELETE n",
+        "MATCH ()-[r:knows|likes]->() DELETE r",
+        "MATCH ()-[r]-() DELETE r",
+    ]
+    .iter()
+    {
+        let ast = parser.parse(query).unwrap();
+        assert!(Builder::new().build(&ast).is_ok(), "{}", query);
+    }
+}
+
+#[test]
+fn test_build_with() {
+    let parser = Parser {};
+    let ast = parser
+        .parse(
+            r#"
+    MATCH (a:Person)
+    WITH a AS b WHERE b.age = 3
+    MATCH (b)-[r:knows]->(c:Person)
+    RETURN c
+    "#,
+        )
+        .unwrap();
+
+    let mut builder = Builder::new();
+    match builder.build(&ast).unwrap() {
+        RelExpr::Projection(project) => match *project.child {
+            // Expand from `b` binded by WITH
+            RelExpr::Expand(expand) => match *expand.child {
+                RelExpr::Selection(_) => {}
+                expr @ _ => panic!("unexpected plan {:#?}", expr),
+            },
+            expr @ _ => panic!("unexpected plan {:#?}", expr),
+        },
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
+}
+
+#[test]
+fn test_build_order_by() {
+    let parser = Parser {};
+    let ast = parser
+        .parse(
+            r#"
+    MATCH (a:Person)
+    RETURN a.name AS name ORDER BY a.age DESC SKIP 1 LIMIT 3
+    "#,
+        )
+        .unwrap();
+
+    let mut builder = Builder::new();
+    match builder.build(&ast).unwrap() {
+        RelExpr::Projection(project) => match *project.child {
+            RelExpr::TopN(top_n) => {
+                assert!(top_n.keys.get(0).unwrap().descending);
+                assert!(top_n.skip.is_some());
+            }
+            expr @ _ => panic!("unexpected plan {:#?}", expr),
+        },
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
+}
+
+#[test]
+fn test_build_union() {
+    let parser = Parser {};
+    let ast = parser
+        .parse(
+            r#"
+    MATCH (a:Person) RETURN a.name AS name
+    UNION
+    MATCH (b:Company) RETURN b.name AS name
+    "#,
+        )
+        .unwrap();
+
+    let mut builder = Builder::new();
+    match builder.build(&ast).unwrap() {
+        RelExpr::Union(union) => {
+            assert!(!union.all);
+            assert_eq!(union.children.len(), 2);
+        }
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
+
+    let ast = parser
+        .parse(
+            r#"
+    MATCH (a:Person) RETURN a.name AS name
+    UNION ALL
+    MATCH (b:Company) RETURN b.name AS title
+    "#,
+        )
+        .unwrap();
+    assert!(Builder::new().build(&ast).is_err());
+}
+
+#[test]
+fn test_build_unwind() {
+    let parser = Parser {};
+    let ast = parser
+        .parse(
+            r#"
+    UNWIND a AS x
+    CREATE (n:Person {name: x})
+    "#,
+        )
+        .unwrap();
+
+    let mut builder = Builder::new();
+    match builder.build(&ast).unwrap() {
+        RelExpr::Create(create) => match *create.child {
+            RelExpr::Unwind(unwind) => {
+                assert_eq!(unwind.name, "x");
+                assert!(matches!(*unwind.child, RelExpr::SingleRow));
+            }
+            expr @ _ => panic!("unexpected plan {:#?}", expr),
+        },
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
+}
+
+#[test]
+fn test_build_optional_match() {
+    let parser = Parser {};
+    let ast = parser
+        .parse(
+            r#"
+    MATCH (p:Person)
+    OPTIONAL MATCH (p)-[:owns]->(c:Car)
+    RETURN p, c
+    "#,
+        )
+        .unwrap();
+
+    let mut builder = Builder::new();
+    match builder.build(&ast).unwrap() {
+        RelExpr::Projection(project) => match *project.child {
+            RelExpr::LeftOuterApply(apply) => {
+                assert!(matches!(*apply.child, RelExpr::NodeScan(_)));
+                assert!(apply.optional_names.contains(&"c".to_owned()));
+                assert!(!apply.optional_names.contains(&"p".to_owned()));
+            }
+            expr @ _ => panic!("unexpected plan {:#?}", expr),
+        },
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
+}
+
+#[test]
+fn test_build_multiple_match() {
+    let parser = Parser {};
+    let ast = parser
+        .parse(
+            r#"
+    MATCH (a:Person)
+    MATCH (a)-[:knows]->(b:Person)
+    MATCH (c:Person)
+    RETURN b, c
+    "#,
+        )
+        .unwrap();
+
+    let mut builder = Builder::new();
+    match builder.build(&ast).unwrap() {
+        RelExpr::Projection(project) => match *project.child {
+            RelExpr::Join(join) => {
+                // Bound variable `a` is expanded instead of scanned again
+                assert!(matches!(*join.lhs, RelExpr::Expand(_)));
+                assert!(matches!(*join.rhs, RelExpr::NodeScan(_)));
+            }
+            expr @ _ => panic!("unexpected plan {:#?}", expr),
+        },
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
+
+    // Bound node is joined with nodes of its new labels
+    let ast = parser
+        .parse("MATCH (a:Person) MATCH (a:Robot)-[:knows]->(b) RETURN b")
+        .unwrap();
+    match Builder::new().build(&ast).unwrap() {
+        RelExpr::Projection(project) => match *project.child {
+            RelExpr::Expand(expand) => match *expand.child {
+                RelExpr::Join(join) => match (*join.lhs, *join.rhs) {
+                    (RelExpr::NodeScan(lhs), RelExpr::NodeScan(rhs)) => {
+                        assert_eq!(lhs.labels, vec!["Person"]);
+                        assert_eq!(rhs.binded_name, "a");
+                        assert_eq!(rhs.labels, vec!["Robot"]);
+                    }
+                    expr @ _ => panic!("unexpected plan {:#?}", expr),
+                },
+                expr @ _ => panic!("unexpected plan {:#?}", expr),
+            },
+            expr @ _ => panic!("unexpected plan {:#?}", expr),
+        },
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
+    let ast = parser
+        .parse("MATCH (a:Person) MATCH (a:Person) RETURN a")
+        .unwrap();
+    match Builder::new().build(&ast).unwrap() {
+        RelExpr::Projection(project) => {
+            assert!(matches!(*project.child, RelExpr::NodeScan(_)))
+        }
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
+}
+
+#[test]
+fn test_build_parameter() {
+    let parser = Parser {};
+    let ast = parser
+        .parse(
+            r#"
+    MATCH (a:Person) WHERE a.age = $age
+    RETURN a LIMIT $limit
+    "#,
+        )
+        .unwrap();
+
+    // Parameters are kept in plan, and their values are given when executing it
+    match Builder::new().build(&ast).unwrap() {
+        RelExpr::Limit(limit) => match limit.limit {
+            Some(ScalarExpr::Parameter(name)) => assert_eq!(name, "limit"),
+            expr @ _ => panic!("unexpected limit {:#?}", expr),
+        },
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
+}
+
+#[test]
+fn test_pushdown() {
+    let parser = Parser {};
+    let ast = parser
+        .parse(
+            r#"
+    MATCH (a:Person)-[r:knows]->(b:Person)
+    WHERE a.name STARTS WITH 'A' AND b.name CONTAINS a.name
+    RETURN b
+    "#,
+        )
+        .unwrap();
+
+    let mut builder = Builder::new();
+    let expr = PredicatePushdown::new().walk(builder.build(&ast).unwrap());
+    match expr {
+        RelExpr::Projection(project) => match *project.child {
+            RelExpr::Selection(select) => {
+                // Predicate on both `a` and `b` is not pushed down
+                assert_eq!(select.filter.len(), 1);
+                match *select.child {
+                    RelExpr::Expand(expand) => match *expand.child {
+                        RelExpr::NodeScan(scan) => assert_eq!(scan.filter.len(), 1),
+                        expr @ _ => panic!("unexpected plan {:#?}", expr),
+                    },
+                    expr @ _ => panic!("unexpected plan {:#?}", expr),
+                }
+            }
+            expr @ _ => panic!("unexpected plan {:#?}", expr),
+        },
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
+}
+
+#[test]
+fn test_build_function_call() {
+    let parser = Parser {};
+    let ast = parser
+        .parse("MATCH (a:Person) RETURN toUpper(a.name) AS name")
+        .unwrap();
+    let mut builder = Builder::new();
+    match builder.build(&ast).unwrap() {
+        RelExpr::Projection(project) => match &project.projects[0].0 {
+            ScalarExpr::FunctionCall(func, args) => {
+                assert_eq!(func.name, "toupper");
+                assert_eq!(args.len(), 1);
+            }
+            expr @ _ => panic!("unexpected expression {:#?}", expr),
+        },
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
+
+    let ast = parser.parse("MATCH (a:Person) RETURN foo(a) AS a").unwrap();
+    assert!(Builder::new().build(&ast).is_err());
+    let ast = parser
+        .parse("MATCH (a:Person) RETURN size(a, a) AS a")
+        .unwrap();
+    assert!(Builder::new().build(&ast).is_err());
+
+    // Types of literal arguments are checked when building plan
+    let ast = parser
+        .parse("MATCH (a:Person) RETURN toUpper(1) AS a")
+        .unwrap();
+    assert!(Builder::new().build(&ast).is_err());
+    let ast = parser
+        .parse("MATCH (a:Person) RETURN size([1, 2]) AS a, toUpper(null) AS b")
+        .unwrap();
+    assert!(Builder::new().build(&ast).is_ok());
+    // Maps are not entities
+    for query in [
+        "RETURN id({id: 1}) AS a",
+        "MATCH (a:Person) RETURN id(a {.id}) AS b",
+    ]
+    .iter()
+    {
+        let ast = parser.parse(query).unwrap();
+        assert!(Builder::new().build(&ast).is_err(), "{}", query);
+    }
+}
+
+#[test]
+fn test_build_aggregate() {
+    let parser = Parser {};
+    let ast = parser
+        .parse(
+            r#"
+    MATCH (a:Person)
+    RETURN a.city AS city, count(*) AS total, count(DISTINCT a.name) AS names
+    "#,
+        )
+        .unwrap();
+    let mut builder = Builder::new();
+    match builder.build(&ast).unwrap() {
+        RelExpr::Projection(project) => {
+            assert!(!project.star);
+            assert_eq!(project.projects.len(), 3);
+            match *project.child {
+                RelExpr::Aggregate(aggregate) => {
+                    assert_eq!(aggregate.group_by.len(), 1);
+                    assert_eq!(aggregate.group_by[0].1, "city");
+                    assert_eq!(aggregate.aggregates.len(), 2);
+                    assert!(aggregate.aggregates[0].args.is_empty());
+                    assert!(aggregate.aggregates[1].distinct);
+                }
+                expr @ _ => panic!("unexpected plan {:#?}", expr),
+            }
+        }
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
+
+    // Aggregation is only allowed in projections and cannot be nested
+    let ast = parser
+        .parse("MATCH (a:Person) WHERE count(*) > 1 RETURN a")
+        .unwrap();
+    assert!(Builder::new().build(&ast).is_err());
+    let ast = parser
+        .parse("MATCH (a:Person) RETURN max(count(*)) AS c")
+        .unwrap();
+    assert!(Builder::new().build(&ast).is_err());
+    let ast = parser
+        .parse("MATCH (a:Person) RETURN toUpper(DISTINCT a.name) AS name")
+        .unwrap();
+    assert!(Builder::new().build(&ast).is_err());
+    // `a` in aggregated item can't be a grouping key, as `a` is another key
+    let ast = parser
+        .parse("MATCH (a:Person) RETURN a.name AS a, a.age + count(*) AS b")
+        .unwrap();
+    assert!(Builder::new().build(&ast).is_err());
+}
+
+// Plan below the projection of `RETURN`, with predicates pushed down
+#[cfg(test)]
+fn build_match(query: &str) -> Result<RelExpr, Error> {
+    let ast = Parser::new().parse(query)?;
+    match PredicatePushdown::new().walk(Builder::new().build(&ast)?) {
+        RelExpr::Projection(project) => Ok(*project.child),
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
+}
+
+#[test]
+fn test_build_var_length_expand() {
+    let cases = vec![
+        ("*", Some((1, None))),
+        ("*2", Some((2, Some(2)))),
+        ("*1..3", Some((1, Some(3)))),
+        ("*..3", Some((1, Some(3)))),
+        ("*2..", Some((2, None))),
+        ("*0..1", Some((0, Some(1)))),
+        ("*2..2", Some((2, Some(2)))),
+        ("*3..2", None),
+    ];
+    for (length, expected) in cases {
+        let query = format!("MATCH (a:Person)-[r:knows{}]->(b:Person) RETURN b", length);
+        let hops = build_match(&query).ok().map(|expr| match expr {
+            RelExpr::VarLengthExpand(expand) => (expand.min_hops, expand.max_hops),
+            expr @ _ => panic!("unexpected plan {:#?}", expr),
+        });
+        assert_eq!(hops, expected, "{}", length);
+    }
+
+    // Properties are predicates on every relationship on the path
+    let query = "MATCH (a:Person)-[r:knows*..3 {since: 2020}]->(b:Person) RETURN b";
+    assert!(matches!(
+        build_match(query).unwrap(),
+        RelExpr::VarLengthExpand(expand) if expand.rel_filter.len() == 1
+    ));
+    let ast = Parser::new()
+        .parse("MATCH (a:Person) CREATE (a)-[:knows*2]->(b:Person)")
+        .unwrap();
+    assert!(Builder::new().build(&ast).is_err());
+}
+
+#[test]
+fn test_build_undirected_expand() {
+    let cases = vec![
+        ("(a:Person)-[r:coauthor]-(b:Person)", ("a", "b", true)),
+        ("(a:Person)-[r:coauthor]->(b:Person)", ("a", "b", false)),
+        ("(a:Person)<-[r:coauthor]-(b:Person)", ("b", "a", false)),
+        ("(a:Person)-[r:coauthor*1..2]-(b:Person)", ("a", "b", true)),
+    ];
+    for (pattern, expected) in cases {
+        let query = format!("MATCH {} RETURN b", pattern);
+        let expand = match build_match(&query).unwrap() {
+            RelExpr::Expand(expand) => (expand.start_name, expand.end_name, expand.undirected),
+            RelExpr::VarLengthExpand(expand) => {
+                (expand.start_name, expand.end_name, expand.undirected)
+            }
+            expr @ _ => panic!("unexpected plan {:#?}", expr),
+        };
+        assert_eq!(
+            expand,
+            (expected.0.to_owned(), expected.1.to_owned(), expected.2),
+            "{}",
+            pattern
+        );
+    }
+
+    let ast = Parser::new()
+        .parse("MATCH (a:Person), (b:Person) CREATE (a)-[:coauthor]-(b)")
+        .unwrap();
+    assert!(Builder::new().build(&ast).is_err());
+}
+
+#[test]
+fn test_build_anonymous_pattern() {
+    // Columns of `RETURN *`, and number of hidden variables below it
+    let cases = vec![
+        (
+            "MATCH (a:Person)-[:knows]->(:Person)-[r:knows]->(b:Person) RETURN *",
+            vec!["a", "b", "r"],
+            2,
+        ),
+        ("MATCH (a)-->()<--() RETURN *", vec!["a"], 4),
+        ("MATCH (a)-->() MATCH (a)-->() RETURN *", vec!["a"], 4),
+        (
+            "MATCH (:Person)-[r]->(b) RETURN * ORDER BY b.name",
+            vec!["b", "r"],
+            1,
+        ),
+        ("MATCH (a)-->() WITH * RETURN *", vec!["a"], 0),
+    ];
+    for (query, expected, hidden) in cases {
+        let ast = Parser::new().parse(query).unwrap();
+        let (mut columns, child) = match Builder::new().build(&ast).unwrap() {
+            RelExpr::Projection(project) => {
+                let columns: Vec<String> = project.projects.into_iter().map(|v| v.1).collect();
+                (columns, project.child)
+            }
+            expr @ _ => panic!("unexpected plan {:#?}", expr),
+        };
+        columns.sort();
+        let mut names: Vec<String> = child
+            .bound_variables()
+            .into_iter()
+            .filter(|v| v.starts_with("__anon"))
+            .collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(columns, expected, "{}", query);
+        assert_eq!(names.len(), hidden, "{}", query);
+    }
+}
+
+// Number of predicates on every variable, from the bottom of plan
+#[cfg(test)]
+fn filter_counts(expr: &RelExpr, counts: &mut Vec<String>) {
+    match expr {
+        RelExpr::NodeScan(scan) => {
+            counts.push(format!("{}: {}", scan.binded_name, scan.filter.len()));
+        }
+        RelExpr::Expand(expand) => {
+            filter_counts(&expand.child, counts);
+            counts.push(format!("{}: {}", expand.rel_name, expand.rel_filter.len()));
+            counts.push(format!("{}: {}", expand.end_name, expand.end_filter.len()));
+        }
+        RelExpr::VarLengthExpand(expand) => {
+            filter_counts(&expand.child, counts);
+            counts.push(format!("{}: {}", expand.rel_name, expand.rel_filter.len()));
+            counts.push(format!("{}: {}", expand.end_name, expand.end_filter.len()));
+        }
+        RelExpr::LeftOuterApply(apply) => {
+            filter_counts(&apply.child, counts);
+            filter_counts(&apply.pattern, counts);
+            counts.push(format!("apply: {}", apply.filter.len()));
+        }
+        RelExpr::Selection(select) => {
+            filter_counts(&select.child, counts);
+            counts.push(format!("where: {}", select.filter.len()));
+        }
+        RelExpr::Projection(project) => filter_counts(&project.child, counts),
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
+}
+
+#[test]
+fn test_pushdown_inline_properties() {
+    let cases = vec![
+        ("MATCH (a:Person {name: 'Alice'}) RETURN a", vec!["a: 1"]),
+        (
+            "MATCH (a:Person {name: 'Alice', age: $age}) RETURN a",
+            vec!["a: 2"],
+        ),
+        ("MATCH (a:Person {name: null}) RETURN a", vec!["a: 1"]),
+        ("MATCH (a {name: 'Alice'}) RETURN a", vec!["a: 1"]),
+        (
+            "MATCH (a:Person)-[r:knows {since: 2020}]->(b:Person {age: 30}) RETURN b",
+            vec!["a: 0", "r: 1", "b: 1"],
+        ),
+        (
+            "MATCH (a:Person)-[r:knows*1..2 {since: 2020}]->(b:Person) RETURN b",
+            vec!["a: 0", "r: 1", "b: 0"],
+        ),
+        (
+            "MATCH (a:Person {name: 'Alice'}) WHERE a.age > 18 RETURN a",
+            vec!["a: 2"],
+        ),
+        (
+            r#"
+    MATCH (a:Person {name: 'Alice'})-[r:knows {since: 2020}]->(b:Person {age: $age})
+    OPTIONAL MATCH (b)-[s:knows]->(c:Person {name: 'Bob'})
+    RETURN c"#,
+            vec!["a: 1", "r: 1", "b: 1", "b: 0", "s: 0", "c: 1", "apply: 0"],
+        ),
+    ];
+    for (query, expected) in cases {
+        let ast = Parser::new().parse(query).unwrap();
+        let expr = PredicatePushdown::new().walk(Builder::new().build(&ast).unwrap());
+        let mut counts = Vec::new();
+        filter_counts(&expr, &mut counts);
+        assert_eq!(counts, expected, "{}", query);
+    }
+}
+
+#[test]
+fn test_build_multiple_labels_and_types() {
+    let parser = Parser {};
+    let ast = parser.parse("MATCH (n) WHERE id(n) = 5 RETURN n").unwrap();
+    let mut builder = Builder::new();
+    match builder.build(&ast).unwrap() {
+        RelExpr::Projection(project) => match *project.child {
+            RelExpr::Selection(select) => match *select.child {
+                RelExpr::NodeScan(scan) => {
+                    assert!(scan.all);
+                    assert!(scan.labels.is_empty());
+                }
+                expr @ _ => panic!("unexpected plan {:#?}", expr),
+            },
+            expr @ _ => panic!("unexpected plan {:#?}", expr),
+        },
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
+
+    let ast = parser
+        .parse("MATCH (a:Person:Author)-[r:knows|likes]->(b) RETURN b")
+        .unwrap();
+    let mut builder = Builder::new();
+    match builder.build(&ast).unwrap() {
+        RelExpr::Projection(project) => match *project.child {
+            RelExpr::Expand(expand) => {
+                assert_eq!(expand.rel_types, vec!["knows", "likes"]);
+                assert!(expand.end_labels.is_empty());
+                match *expand.child {
+                    RelExpr::NodeScan(scan) => {
+                        assert!(!scan.all);
+                        assert_eq!(scan.labels, vec!["Person", "Author"]);
+                    }
+                    expr @ _ => panic!("unexpected plan {:#?}", expr),
+                }
+            }
+            expr @ _ => panic!("unexpected plan {:#?}", expr),
+        },
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
+
+    // Tables of updated entities are resolved when updating them
+    let ast = parser
+        .parse("MATCH (a)-[r:knows|likes]->(b) SET r.since = 2020")
+        .unwrap();
+    assert!(Builder::new().build(&ast).is_ok());
+    let ast = parser.parse("MATCH (n) SET n.name = 'a'").unwrap();
+    assert!(Builder::new().build(&ast).is_ok());
+}
+
+#[test]
+fn test_build_arithmetic() {
+    let parser = Parser {};
+    let ast = parser
+        .parse("MATCH (a:Person) WHERE a.age - 1 >= 18 RETURN -a.age * 2 + 1 AS b")
+        .unwrap();
+    let mut builder = Builder::new();
+    match builder.build(&ast).unwrap() {
+        RelExpr::Projection(project) => {
+            match &project.projects[0].0 {
+                ScalarExpr::Add(lhs, _) => match lhs.as_ref() {
+                    ScalarExpr::Mult(lhs, _) => {
+                        assert!(matches!(lhs.as_ref(), ScalarExpr::Negate(_)))
+                    }
+                    expr @ _ => panic!("unexpected expression {:#?}", expr),
+                },
+                expr @ _ => panic!("unexpected expression {:#?}", expr),
+            }
+            match *project.child {
+                RelExpr::Selection(select) => {
+                    assert!(
+                        matches!(&select.filter[0], ScalarExpr::GreaterEqual(lhs, _) if matches!(lhs.as_ref(), ScalarExpr::Sub(_, _)))
+                    );
+                }
+                expr @ _ => panic!("unexpected plan {:#?}", expr),
+            }
+        }
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
+}
+
+#[test]
+fn test_build_map_projection() {
+    let parser = Parser {};
+    let ast = parser
+        .parse("MATCH (a:Person)-[:knows]->(b:Person) RETURN a {.name, .*, friends: count(b)} AS a")
+        .unwrap();
+    let mut builder = Builder::new();
+    match builder.build(&ast).unwrap() {
+        RelExpr::Projection(project) => match &project.projects[0].0 {
+            ScalarExpr::MapProjection(child, all, entries) => {
+                assert!(matches!(child.as_ref(), ScalarExpr::Variable(name) if name == "a"));
+                assert!(*all);
+                assert_eq!(entries.len(), 2);
+                assert!(
+                    matches!(&entries[0].1, ScalarExpr::PropertyLookup(_, key) if key == "name")
+                );
+                assert_eq!(entries[1].0, "friends");
+                // `a` is referred outside of count(), so rows are grouped by it
+                match project.child.as_ref() {
+                    RelExpr::Aggregate(aggregate) => {
+                        assert_eq!(aggregate.group_by.len(), 1);
+                        assert!(
+                            matches!(&aggregate.group_by[0].0, ScalarExpr::Variable(name) if name == "a")
+                        );
+                        assert_eq!(aggregate.group_by[0].1, "a");
+                        assert_eq!(aggregate.aggregates.len(), 1);
+                    }
+                    expr @ _ => panic!("unexpected plan {:#?}", expr),
+                }
+            }
+            expr @ _ => panic!("unexpected expression {:#?}", expr),
+        },
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
+}
+
+#[test]
+fn test_pushdown_merge() {
+    let parser = Parser {};
+    let ast = parser
+        .parse("MATCH (a:Person) MERGE (a)-[:knows]->(b:Person {name: 'Bob'})")
+        .unwrap();
+    let mut builder = Builder::new();
+    let expr = PredicatePushdown::new().walk(builder.build(&ast).unwrap());
+    match expr {
+        RelExpr::Merge(merge) => {
+            assert!(merge.filter.is_empty());
+            match *merge.pattern {
+                RelExpr::Expand(expand) => assert_eq!(expand.end_filter.len(), 1),
+                expr @ _ => panic!("unexpected plan {:#?}", expr),
+            }
+        }
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    }
 }
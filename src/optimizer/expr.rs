@@ -1,3 +1,5 @@
+use crate::runtime::{AggregateFunction, ScalarFunction};
+
 // pub trait Memo {
 //     fn init(&mut self);
 //     fn memonize(plan: Box<Plan>) -> Box<Plan>;
@@ -16,29 +18,166 @@ pub enum RelExpr {
     NodeScan(ScanExpr),
     Join(JoinExpr),
     Expand(ExpandExpr),
+    VarLengthExpand(VarLengthExpandExpr),
+    LeftOuterApply(LeftOuterApplyExpr),
     Selection(SelectExpr),
     Projection(ProjectExpr),
+    Aggregate(AggregateExpr),
+    Sort(SortExpr),
+    TopN(TopNExpr),
+    Limit(LimitExpr),
+    Unwind(UnwindExpr),
+    Union(UnionExpr),
+    Create(CreateExpr),
+    Merge(MergeExpr),
+    Update(UpdateExpr),
+    Delete(DeleteExpr),
+
+    // Produces exactly one empty row, used as input of clauses without
+    // reading clause, e.g. `CREATE (a:Person)`
+    SingleRow,
+}
+
+impl RelExpr {
+    // Names of variables binded by rows of this expression
+    pub fn bound_variables(&self) -> Vec<String> {
+        match self {
+            RelExpr::NodeScan(scan) => vec![scan.binded_name.to_owned()],
+            RelExpr::Join(join) => {
+                let mut names = join.lhs.bound_variables();
+                names.extend(join.rhs.bound_variables());
+                names
+            }
+            RelExpr::Expand(expand) => {
+                let mut names = expand.child.bound_variables();
+                names.push(expand.rel_name.to_owned());
+                names.push(expand.end_name.to_owned());
+                names
+            }
+            RelExpr::VarLengthExpand(expand) => {
+                let mut names = expand.child.bound_variables();
+                names.push(expand.rel_name.to_owned());
+                names.push(expand.end_name.to_owned());
+                names
+            }
+            RelExpr::LeftOuterApply(apply) => {
+                let mut names = apply.child.bound_variables();
+                names.extend(apply.optional_names.iter().cloned());
+                names
+            }
+            RelExpr::Selection(select) => select.child.bound_variables(),
+            RelExpr::Projection(project) => {
+                let mut names = if project.star {
+                    project.child.bound_variables()
+                } else {
+                    Vec::new()
+                };
+                names.extend(project.projects.iter().map(|v| v.1.to_owned()));
+                names
+            }
+            RelExpr::Aggregate(aggregate) => {
+                let mut names: Vec<String> =
+                    aggregate.group_by.iter().map(|v| v.1.to_owned()).collect();
+                names.extend(aggregate.aggregates.iter().map(|v| v.name.to_owned()));
+                names
+            }
+            RelExpr::Sort(sort) => sort.child.bound_variables(),
+            RelExpr::TopN(top_n) => top_n.child.bound_variables(),
+            RelExpr::Limit(limit) => limit.child.bound_variables(),
+            RelExpr::Unwind(unwind) => {
+                let mut names = unwind.child.bound_variables();
+                names.push(unwind.name.to_owned());
+                names
+            }
+            RelExpr::Union(union) => union.children[0].bound_variables(),
+            RelExpr::Create(create) => {
+                let mut names = create.child.bound_variables();
+                names.extend(create.nodes.iter().map(|v| v.name.to_owned()));
+                names.extend(create.rels.iter().filter_map(|v| v.name.to_owned()));
+                names
+            }
+            RelExpr::Merge(merge) => {
+                let mut names = merge.child.bound_variables();
+                for name in merge.pattern.bound_variables().into_iter() {
+                    if !names.contains(&name) {
+                        names.push(name);
+                    }
+                }
+                names
+            }
+            RelExpr::Update(update) => update.child.bound_variables(),
+            RelExpr::Delete(delete) => delete.child.bound_variables(),
+            RelExpr::SingleRow => Vec::new(),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum ScalarExpr {
-    // Add(AddExpr),
-    // Sub(SubExpr),
-    // Mult(MultiplyExpr),
-    // Div(DivideExpr),
+    Add(Box<ScalarExpr>, Box<ScalarExpr>),
+    Sub(Box<ScalarExpr>, Box<ScalarExpr>),
+    Mult(Box<ScalarExpr>, Box<ScalarExpr>),
+    Div(Box<ScalarExpr>, Box<ScalarExpr>),
+    Mod(Box<ScalarExpr>, Box<ScalarExpr>),
+    Power(Box<ScalarExpr>, Box<ScalarExpr>),
+    Negate(Box<ScalarExpr>),
     Equal(Box<ScalarExpr>, Box<ScalarExpr>),
+    NotEqual(Box<ScalarExpr>, Box<ScalarExpr>),
+    Less(Box<ScalarExpr>, Box<ScalarExpr>),
+    LessEqual(Box<ScalarExpr>, Box<ScalarExpr>),
+    Greater(Box<ScalarExpr>, Box<ScalarExpr>),
+    GreaterEqual(Box<ScalarExpr>, Box<ScalarExpr>),
     LogicAnd(Box<ScalarExpr>, Box<ScalarExpr>),
+    LogicOr(Box<ScalarExpr>, Box<ScalarExpr>),
+    LogicXor(Box<ScalarExpr>, Box<ScalarExpr>),
+    LogicNot(Box<ScalarExpr>),
+    IsNull(Box<ScalarExpr>),
+    IsNotNull(Box<ScalarExpr>),
+    StartsWith(Box<ScalarExpr>, Box<ScalarExpr>),
+    EndsWith(Box<ScalarExpr>, Box<ScalarExpr>),
+    Contains(Box<ScalarExpr>, Box<ScalarExpr>),
+    RegexMatch(Box<ScalarExpr>, Box<ScalarExpr>),
+    In(Box<ScalarExpr>, Box<ScalarExpr>),
     PropertyLookup(Box<ScalarExpr>, String),
     Variable(String),
+    // Parameter, whose value is given when executing plan
+    Parameter(String),
+    // Function resolved when building plan
+    FunctionCall(&'static ScalarFunction, Vec<ScalarExpr>),
+    // Operand, alternatives of WHEN and THEN, and ELSE
+    Case(
+        Option<Box<ScalarExpr>>,
+        Vec<(ScalarExpr, ScalarExpr)>,
+        Option<Box<ScalarExpr>>,
+    ),
+    IntegerLiteral(i64),
     NumberLiteral(f64),
     StringLiteral(String),
+    BooleanLiteral(bool),
+    NullLiteral,
+    ListLiteral(Vec<ScalarExpr>),
+    MapLiteral(Vec<(String, ScalarExpr)>),
+    // Map projection of entity or map, with all its properties if `.*` is
+    // given, and entries evaluated from the row
+    MapProjection(Box<ScalarExpr>, bool, Vec<(String, ScalarExpr)>),
+    Subscript(Box<ScalarExpr>, Box<ScalarExpr>),
+    Slice(
+        Box<ScalarExpr>,
+        Option<Box<ScalarExpr>>,
+        Option<Box<ScalarExpr>>,
+    ),
 }
 
 #[derive(Debug)]
 pub struct ScanExpr {
     pub binded_name: String,
+    // Scan nodes of every label
     pub all: bool,
-    pub label: String,
+    // Scanned nodes have all of the labels
+    pub labels: Vec<String>,
+    // Predicates on the scanned variable, which are pushed down to TiDB if
+    // possible
+    pub filter: Vec<ScalarExpr>,
 }
 
 #[derive(Debug)]
@@ -46,13 +185,42 @@ pub struct ExpandExpr {
     pub start_name: String,
     pub end_name: String,
     pub rel_name: String,
-    pub rel_type: String,
-    pub end_label: String,
+    // Relationships of any of the types are expanded, or every type if empty
+    pub rel_types: Vec<String>,
+    // End nodes have all of the labels, or any label if empty
+    pub end_labels: Vec<String>,
+    // Relationships can be traversed from either side
+    pub undirected: bool,
+    // Predicates on the relationship and the end node, which are evaluated
+    // while fetching them
+    pub rel_filter: Vec<ScalarExpr>,
+    pub end_filter: Vec<ScalarExpr>,
 
     pub child: Box<RelExpr>,
 }
 
+// Expand paths of `min_hops` to `max_hops` relationships, a relationship is
+// traversed at most once by each path. The relationship variable is binded
+// to list of relationships on the path.
 #[derive(Debug)]
+pub struct VarLengthExpandExpr {
+    pub start_name: String,
+    pub end_name: String,
+    pub rel_name: String,
+    pub rel_types: Vec<String>,
+    pub end_labels: Vec<String>,
+    pub min_hops: u64,
+    // Unbounded if not given
+    pub max_hops: Option<u64>,
+    pub undirected: bool,
+    // Predicates on every relationship on the path
+    pub rel_filter: Vec<ScalarExpr>,
+    pub end_filter: Vec<ScalarExpr>,
+
+    pub child: Box<RelExpr>,
+}
+
+#[derive(Debug, Clone)]
 pub enum JoinType {
     CartesianProduct,
 }
@@ -81,6 +249,190 @@ pub struct ProjectExpr {
     pub child: Box<RelExpr>,
 }
 
+// Rows are grouped by values of `group_by`, and results of aggregation
+// functions are binded to their names for every group
+#[derive(Debug)]
+pub struct AggregateExpr {
+    pub group_by: Vec<(ScalarExpr, String)>,
+    pub aggregates: Vec<AggregateCall>,
+
+    pub child: Box<RelExpr>,
+}
+
+// Call of aggregation function, count(*) is a call of count without arguments
+#[derive(Debug, Clone)]
+pub struct AggregateCall {
+    pub func: AggregateFunction,
+    pub args: Vec<ScalarExpr>,
+    pub distinct: bool,
+    pub name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SortKey {
+    pub expr: ScalarExpr,
+    pub descending: bool,
+}
+
+#[derive(Debug)]
+pub struct SortExpr {
+    pub keys: Vec<SortKey>,
+
+    pub child: Box<RelExpr>,
+}
+
+// Sort with limit, only the first `skip + limit` rows are kept while sorting
+#[derive(Debug)]
+pub struct TopNExpr {
+    pub keys: Vec<SortKey>,
+    pub skip: Option<ScalarExpr>,
+    pub limit: ScalarExpr,
+
+    pub child: Box<RelExpr>,
+}
+
+#[derive(Debug)]
+pub struct LimitExpr {
+    pub skip: Option<ScalarExpr>,
+    pub limit: Option<ScalarExpr>,
+
+    pub child: Box<RelExpr>,
+}
+
+// Match pattern for every input row, rows without any match are kept with
+// variables introduced by pattern binded to null
+#[derive(Debug)]
+pub struct LeftOuterApplyExpr {
+    pub pattern: Box<RelExpr>,
+    pub filter: Vec<ScalarExpr>,
+    pub optional_names: Vec<String>,
+
+    pub child: Box<RelExpr>,
+}
+
+// Expand list into rows, each element of the list is binded to `name`
+#[derive(Debug)]
+pub struct UnwindExpr {
+    pub expr: ScalarExpr,
+    pub name: String,
+
+    pub child: Box<RelExpr>,
+}
+
+// Rows of children are concatenated, and duplicated rows are removed unless
+// `all` is set
+#[derive(Debug)]
+pub struct UnionExpr {
+    pub all: bool,
+
+    pub children: Vec<RelExpr>,
+}
+
+#[derive(Debug)]
+pub struct CreateExpr {
+    // Nodes to be created, which are not binded by child
+    pub nodes: Vec<CreateNode>,
+    pub rels: Vec<CreateRel>,
+
+    pub child: Box<RelExpr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateNode {
+    pub name: String,
+    pub label: String,
+    pub properties: Vec<(String, ScalarExpr)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateRel {
+    pub name: Option<String>,
+    pub rel_type: String,
+    pub start_name: String,
+    pub end_name: String,
+    pub properties: Vec<(String, ScalarExpr)>,
+}
+
+#[derive(Debug)]
+pub struct MergeExpr {
+    // Plan matching the pattern, whose rows are correlated with rows of
+    // child by binded variables
+    pub pattern: Box<RelExpr>,
+    // Predicates of inline properties, evaluated on correlated rows
+    pub filter: Vec<ScalarExpr>,
+    // Entities to be created if nothing matches
+    pub nodes: Vec<CreateNode>,
+    pub rels: Vec<CreateRel>,
+    pub on_match: Vec<UpdateItem>,
+    pub on_create: Vec<UpdateItem>,
+
+    pub child: Box<RelExpr>,
+}
+
+#[derive(Debug, Clone)]
+pub enum UpdateItem {
+    // Set property of node or relationship binded to `name`, tables of the
+    // entity are resolved when updating it
+    Property {
+        name: String,
+        key: String,
+        value: ScalarExpr,
+    },
+    // Set properties in map, and remove other properties if `replace` is true
+    Properties {
+        name: String,
+        properties: Vec<(String, ScalarExpr)>,
+        replace: bool,
+    },
+    RemoveProperty {
+        name: String,
+        key: String,
+    },
+    AddLabels {
+        name: String,
+        labels: Vec<String>,
+    },
+    RemoveLabels {
+        name: String,
+        labels: Vec<String>,
+    },
+}
+
+impl UpdateItem {
+    // Variable of the updated entity
+    pub fn name(&self) -> &String {
+        match self {
+            UpdateItem::Property { name, .. }
+            | UpdateItem::Properties { name, .. }
+            | UpdateItem::RemoveProperty { name, .. }
+            | UpdateItem::AddLabels { name, .. }
+            | UpdateItem::RemoveLabels { name, .. } => name,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DeleteExpr {
+    pub items: Vec<DeleteItem>,
+    // Delete relationships of deleted nodes as well
+    pub detach: bool,
+
+    pub child: Box<RelExpr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeleteItem {
+    pub name: String,
+    pub relationship: bool,
+}
+
+#[derive(Debug)]
+pub struct UpdateExpr {
+    pub items: Vec<UpdateItem>,
+
+    pub child: Box<RelExpr>,
+}
+
 // pub struct Memo {
 //     root: Option<RelExpr>,
 //     rel_idgen: IdGen,
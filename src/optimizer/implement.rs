@@ -1,23 +1,120 @@
-use crate::runtime::{Executor, FilterExec, ProjectExec, TiDBNestedLoopExpand, TiDBScanExec};
+use std::{cell::RefCell, rc::Rc};
+
+use crate::runtime::{
+    Executor, FilterExec, HashAggregateExec, LimitExec, NestedLoopJoin, NestedLoopLeftOuterApply,
+    Params, ProjectExec, Session, SingleRowExec, SortExec, TiDBCreateExec, TiDBDeleteExec,
+    TiDBMergeExec, TiDBNestedLoopExpand, TiDBScanExec, TiDBUpdateExec, TiDBVarLengthExpand,
+    TopNExec, UnionExec, UnwindExec,
+};
 use crate::Error;
 
 use super::expr::RelExpr;
 
-pub fn default_implementation(rel_expr: &RelExpr) -> Result<Box<dyn Executor>, Error> {
+pub fn default_implementation(
+    rel_expr: &RelExpr,
+    params: &Params,
+) -> Result<Box<dyn Executor>, Error> {
+    implement(rel_expr, &None, params)
+}
+
+// Executors accessing TiDB run in the transaction of session if it's given.
+// Parameters are given to executors creating rows without input rows.
+fn implement(
+    rel_expr: &RelExpr,
+    session: &Option<Session>,
+    params: &Params,
+) -> Result<Box<dyn Executor>, Error> {
     match rel_expr {
-        RelExpr::NodeScan(scan) => Ok(Box::new(TiDBScanExec::new(scan))),
+        RelExpr::NodeScan(scan) => Ok(Box::new(TiDBScanExec::new(
+            scan,
+            session.to_owned(),
+            params,
+        ))),
         RelExpr::Expand(expand) => Ok(Box::new(TiDBNestedLoopExpand::new(
-            default_implementation(expand.child.as_ref())?,
+            implement(expand.child.as_ref(), session, params)?,
             expand,
+            session.to_owned(),
+            params,
+        ))),
+        RelExpr::VarLengthExpand(expand) => Ok(Box::new(TiDBVarLengthExpand::new(
+            implement(expand.child.as_ref(), session, params)?,
+            expand,
+            session.to_owned(),
+            params,
+        ))),
+        RelExpr::Join(join) => Ok(Box::new(NestedLoopJoin::new(
+            implement(&join.lhs, session, params)?,
+            implement(&join.rhs, session, params)?,
+            join,
         ))),
         RelExpr::Projection(project) => Ok(Box::new(ProjectExec::new(
-            default_implementation(&project.child)?,
+            implement(&project.child, session, params)?,
             project,
         ))),
+        RelExpr::Aggregate(aggregate) => Ok(Box::new(HashAggregateExec::new(
+            implement(&aggregate.child, session, params)?,
+            aggregate,
+            params,
+        ))),
+        RelExpr::LeftOuterApply(apply) => Ok(Box::new(NestedLoopLeftOuterApply::new(
+            implement(&apply.child, session, params)?,
+            implement(&apply.pattern, session, params)?,
+            apply,
+        ))),
         RelExpr::Selection(select) => Ok(Box::new(FilterExec::new(
-            default_implementation(&select.child)?,
+            implement(&select.child, session, params)?,
             select,
         ))),
-        _ => unimplemented!(),
+        RelExpr::Sort(sort) => Ok(Box::new(SortExec::new(
+            implement(&sort.child, session, params)?,
+            sort,
+        ))),
+        RelExpr::TopN(top_n) => Ok(Box::new(TopNExec::new(
+            implement(&top_n.child, session, params)?,
+            top_n,
+            params,
+        ))),
+        RelExpr::Limit(limit) => Ok(Box::new(LimitExec::new(
+            implement(&limit.child, session, params)?,
+            limit,
+            params,
+        ))),
+        RelExpr::Unwind(unwind) => Ok(Box::new(UnwindExec::new(
+            implement(&unwind.child, session, params)?,
+            unwind,
+        ))),
+        RelExpr::Union(union) => {
+            let mut children = Vec::new();
+            for child in union.children.iter() {
+                children.push(implement(child, session, params)?);
+            }
+            Ok(Box::new(UnionExec::new(children, union)))
+        }
+        RelExpr::Create(create) => Ok(Box::new(TiDBCreateExec::new(
+            implement(&create.child, session, params)?,
+            create,
+        ))),
+        RelExpr::Merge(merge) => {
+            let merge_session: Session = Rc::new(RefCell::new(None));
+            Ok(Box::new(TiDBMergeExec::new(
+                implement(&merge.child, session, params)?,
+                implement(&merge.pattern, &Some(merge_session.clone()), params)?,
+                merge,
+                merge_session,
+            )))
+        }
+        RelExpr::Update(update) => {
+            let update_session: Session = Rc::new(RefCell::new(None));
+            Ok(Box::new(TiDBUpdateExec::new(
+                implement(&update.child, &Some(update_session.clone()), params)?,
+                update,
+                update_session,
+            )))
+        }
+        RelExpr::Delete(delete) => Ok(Box::new(TiDBDeleteExec::new(
+            implement(&delete.child, session, params)?,
+            delete,
+        ))),
+        RelExpr::SingleRow => Ok(Box::new(SingleRowExec::new(params))),
     }
 }
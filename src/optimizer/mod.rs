@@ -2,9 +2,11 @@ mod builder;
 mod expr;
 mod implement;
 mod metadata;
+mod pushdown;
 
 pub use builder::Builder;
+pub use pushdown::PredicatePushdown;
 
 pub use expr::*;
 
-pub use implement::*;
\ No newline at end of file
+pub use implement::*;
@@ -0,0 +1,260 @@
+use super::expr::*;
+use crate::util::Walker;
+
+// Push predicates of selections down to the scans and expands binding the
+// variables they refer to, so that they can be evaluated by TiDB.
+pub struct PredicatePushdown {}
+
+impl PredicatePushdown {
+    pub fn new() -> PredicatePushdown {
+        PredicatePushdown {}
+    }
+
+    fn push_down(&mut self, child: RelExpr, filter: Vec<ScalarExpr>) -> RelExpr {
+        let mut child = child;
+        let remained = self.push_predicates(&mut child, filter);
+
+        if remained.is_empty() {
+            child
+        } else {
+            RelExpr::Selection(SelectExpr {
+                filter: remained,
+                child: Box::new(child),
+            })
+        }
+    }
+
+    // Returns predicates which cannot be pushed down
+    fn push_predicates(&mut self, child: &mut RelExpr, filter: Vec<ScalarExpr>) -> Vec<ScalarExpr> {
+        let mut predicates = Vec::new();
+        for predicate in filter.into_iter() {
+            split_conjunction(predicate, &mut predicates);
+        }
+
+        let mut remained = Vec::new();
+        for predicate in predicates.into_iter() {
+            let names = variables(&predicate);
+            if names.len() == 1 && push_to_scan(child, &names[0], &predicate) {
+                continue;
+            }
+            remained.push(predicate);
+        }
+        remained
+    }
+}
+
+impl Walker<RelExpr> for PredicatePushdown {
+    fn walk(&mut self, expr: RelExpr) -> RelExpr {
+        match expr {
+            RelExpr::Selection(select) => {
+                let child = self.walk(*select.child);
+                self.push_down(child, select.filter)
+            }
+            RelExpr::Join(mut join) => {
+                join.lhs = Box::new(self.walk(*join.lhs));
+                join.rhs = Box::new(self.walk(*join.rhs));
+                RelExpr::Join(join)
+            }
+            RelExpr::Expand(mut expand) => {
+                expand.child = Box::new(self.walk(*expand.child));
+                RelExpr::Expand(expand)
+            }
+            RelExpr::VarLengthExpand(mut expand) => {
+                expand.child = Box::new(self.walk(*expand.child));
+                RelExpr::VarLengthExpand(expand)
+            }
+            RelExpr::LeftOuterApply(mut apply) => {
+                // Pattern rows failing predicates on single variable never
+                // match, so these predicates can be pushed into pattern
+                let mut pattern = self.walk(*apply.pattern);
+                apply.filter = self.push_predicates(&mut pattern, apply.filter);
+                apply.pattern = Box::new(pattern);
+                apply.child = Box::new(self.walk(*apply.child));
+                RelExpr::LeftOuterApply(apply)
+            }
+            RelExpr::Projection(mut project) => {
+                project.child = Box::new(self.walk(*project.child));
+                RelExpr::Projection(project)
+            }
+            RelExpr::Aggregate(mut aggregate) => {
+                aggregate.child = Box::new(self.walk(*aggregate.child));
+                RelExpr::Aggregate(aggregate)
+            }
+            RelExpr::Sort(mut sort) => {
+                sort.child = Box::new(self.walk(*sort.child));
+                RelExpr::Sort(sort)
+            }
+            RelExpr::TopN(mut top_n) => {
+                top_n.child = Box::new(self.walk(*top_n.child));
+                RelExpr::TopN(top_n)
+            }
+            RelExpr::Limit(mut limit) => {
+                limit.child = Box::new(self.walk(*limit.child));
+                RelExpr::Limit(limit)
+            }
+            RelExpr::Unwind(mut unwind) => {
+                unwind.child = Box::new(self.walk(*unwind.child));
+                RelExpr::Unwind(unwind)
+            }
+            RelExpr::Union(mut union) => {
+                union.children = union.children.into_iter().map(|v| self.walk(v)).collect();
+                RelExpr::Union(union)
+            }
+            RelExpr::Create(mut create) => {
+                create.child = Box::new(self.walk(*create.child));
+                RelExpr::Create(create)
+            }
+            RelExpr::Merge(mut merge) => {
+                // Like OPTIONAL MATCH, predicates on single variable are
+                // pushed into pattern, so that only matching rows are locked
+                let mut pattern = self.walk(*merge.pattern);
+                merge.filter = self.push_predicates(&mut pattern, merge.filter);
+                merge.pattern = Box::new(pattern);
+                merge.child = Box::new(self.walk(*merge.child));
+                RelExpr::Merge(merge)
+            }
+            RelExpr::Update(mut update) => {
+                update.child = Box::new(self.walk(*update.child));
+                RelExpr::Update(update)
+            }
+            RelExpr::Delete(mut delete) => {
+                delete.child = Box::new(self.walk(*delete.child));
+                RelExpr::Delete(delete)
+            }
+            expr @ RelExpr::NodeScan(_) | expr @ RelExpr::SingleRow => expr,
+        }
+    }
+}
+
+fn split_conjunction(expr: ScalarExpr, result: &mut Vec<ScalarExpr>) {
+    match expr {
+        ScalarExpr::LogicAnd(lhs, rhs) => {
+            split_conjunction(*lhs, result);
+            split_conjunction(*rhs, result);
+        }
+        expr @ _ => result.push(expr),
+    }
+}
+
+// Only operators keeping the binding of scanned variable unchanged are passed
+// through
+fn push_to_scan(expr: &mut RelExpr, name: &String, predicate: &ScalarExpr) -> bool {
+    match expr {
+        RelExpr::NodeScan(scan) if &scan.binded_name == name => {
+            scan.filter.push(predicate.to_owned());
+            true
+        }
+        RelExpr::Selection(select) => push_to_scan(&mut select.child, name, predicate),
+        RelExpr::Expand(expand) => {
+            if &expand.rel_name == name {
+                expand.rel_filter.push(predicate.to_owned());
+                true
+            } else if &expand.end_name == name {
+                // End node may have been binded by child
+                if !push_to_scan(&mut expand.child, name, predicate) {
+                    expand.end_filter.push(predicate.to_owned());
+                }
+                true
+            } else {
+                push_to_scan(&mut expand.child, name, predicate)
+            }
+        }
+        RelExpr::VarLengthExpand(expand) => {
+            // Relationship variable is binded to list of relationships
+            if &expand.rel_name == name {
+                false
+            } else if &expand.end_name == name {
+                if !push_to_scan(&mut expand.child, name, predicate) {
+                    expand.end_filter.push(predicate.to_owned());
+                }
+                true
+            } else {
+                push_to_scan(&mut expand.child, name, predicate)
+            }
+        }
+        RelExpr::Join(join) => {
+            push_to_scan(&mut join.lhs, name, predicate)
+                || push_to_scan(&mut join.rhs, name, predicate)
+        }
+        _ => false,
+    }
+}
+
+// Variables referred by scalar expression
+pub(crate) fn variables(expr: &ScalarExpr) -> Vec<String> {
+    let mut names = Vec::new();
+    collect_variables(expr, &mut names);
+    names.dedup();
+    names
+}
+
+fn collect_variables(expr: &ScalarExpr, names: &mut Vec<String>) {
+    match expr {
+        ScalarExpr::Variable(name) => {
+            if !names.contains(name) {
+                names.push(name.to_owned());
+            }
+        }
+        ScalarExpr::Equal(lhs, rhs)
+        | ScalarExpr::NotEqual(lhs, rhs)
+        | ScalarExpr::Less(lhs, rhs)
+        | ScalarExpr::LessEqual(lhs, rhs)
+        | ScalarExpr::Greater(lhs, rhs)
+        | ScalarExpr::GreaterEqual(lhs, rhs)
+        | ScalarExpr::LogicAnd(lhs, rhs)
+        | ScalarExpr::LogicOr(lhs, rhs)
+        | ScalarExpr::LogicXor(lhs, rhs)
+        | ScalarExpr::StartsWith(lhs, rhs)
+        | ScalarExpr::EndsWith(lhs, rhs)
+        | ScalarExpr::Contains(lhs, rhs)
+        | ScalarExpr::RegexMatch(lhs, rhs)
+        | ScalarExpr::In(lhs, rhs)
+        | ScalarExpr::Add(lhs, rhs)
+        | ScalarExpr::Sub(lhs, rhs)
+        | ScalarExpr::Mult(lhs, rhs)
+        | ScalarExpr::Div(lhs, rhs)
+        | ScalarExpr::Mod(lhs, rhs)
+        | ScalarExpr::Power(lhs, rhs)
+        | ScalarExpr::Subscript(lhs, rhs) => {
+            collect_variables(lhs, names);
+            collect_variables(rhs, names);
+        }
+        ScalarExpr::LogicNot(child)
+        | ScalarExpr::IsNull(child)
+        | ScalarExpr::IsNotNull(child)
+        | ScalarExpr::Negate(child)
+        | ScalarExpr::PropertyLookup(child, _) => collect_variables(child, names),
+        ScalarExpr::Slice(child, from, to) => {
+            collect_variables(child, names);
+            from.iter().for_each(|v| collect_variables(v, names));
+            to.iter().for_each(|v| collect_variables(v, names));
+        }
+        ScalarExpr::Case(operand, alternatives, default) => {
+            operand.iter().for_each(|v| collect_variables(v, names));
+            for (when, then) in alternatives.iter() {
+                collect_variables(when, names);
+                collect_variables(then, names);
+            }
+            default.iter().for_each(|v| collect_variables(v, names));
+        }
+        ScalarExpr::FunctionCall(_, args) => {
+            args.iter().for_each(|v| collect_variables(v, names));
+        }
+        ScalarExpr::ListLiteral(elements) => {
+            elements.iter().for_each(|v| collect_variables(v, names));
+        }
+        ScalarExpr::MapLiteral(entries) => {
+            entries.iter().for_each(|v| collect_variables(&v.1, names));
+        }
+        ScalarExpr::MapProjection(child, _, entries) => {
+            collect_variables(child, names);
+            entries.iter().for_each(|v| collect_variables(&v.1, names));
+        }
+        ScalarExpr::Parameter(_)
+        | ScalarExpr::IntegerLiteral(_)
+        | ScalarExpr::NumberLiteral(_)
+        | ScalarExpr::StringLiteral(_)
+        | ScalarExpr::BooleanLiteral(_)
+        | ScalarExpr::NullLiteral => {}
+    }
+}
@@ -1,6 +1,6 @@
 use std::fmt;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Expr {
     EqualExpr(EqualExpr),
     NotEqualExpr(NotEqualExpr),
@@ -13,20 +13,40 @@ pub enum Expr {
     XorExpr(XorExpr),
     OrExpr(OrExpr),
     NotExpr(NotExpr),
+    IsNullExpr(IsNullExpr),
+    IsNotNullExpr(IsNotNullExpr),
+    StartsWithExpr(StartsWithExpr),
+    EndsWithExpr(EndsWithExpr),
+    ContainsExpr(ContainsExpr),
+    RegexMatchExpr(RegexMatchExpr),
+    InExpr(InExpr),
 
     AddExpr(AddExpr),
     SubExpr(SubExpr),
     MultExpr(MultExpr),
     DivExpr(DivExpr),
+    ModExpr(ModExpr),
     PowerExpr(PowerExpr),
     UnarySubExpr(UnarySubExpr),
 
+    IntegerLit(i64),
     NumberLit(f64),
     StringLit(String),
     BooleanLit(bool),
+    NullLit,
+    ListLit(Vec<Expr>),
+    MapLit(Vec<(String, Expr)>),
 
     Variable(String),
+    Parameter(String),
+    CaseExpr(CaseExpr),
+    FunctionCall(FunctionCall),
+    // count(*)
+    CountStar,
     PropertyLookup(PropertyLookup),
+    Subscript(Subscript),
+    Slice(Slice),
+    MapProjection(MapProjection),
 }
 
 impl fmt::Display for Expr {
@@ -42,117 +62,277 @@ impl fmt::Display for Expr {
             Self::XorExpr(expr) => write!(fmt, "{} XOR {}", expr.lhs, expr.rhs),
             Self::OrExpr(expr) => write!(fmt, "{} OR {}", expr.lhs, expr.rhs),
             Self::NotExpr(expr) => write!(fmt, "NOT {}", expr.child),
+            Self::IsNullExpr(expr) => write!(fmt, "{} IS NULL", expr.child),
+            Self::IsNotNullExpr(expr) => write!(fmt, "{} IS NOT NULL", expr.child),
+            Self::StartsWithExpr(expr) => write!(fmt, "{} STARTS WITH {}", expr.lhs, expr.rhs),
+            Self::EndsWithExpr(expr) => write!(fmt, "{} ENDS WITH {}", expr.lhs, expr.rhs),
+            Self::ContainsExpr(expr) => write!(fmt, "{} CONTAINS {}", expr.lhs, expr.rhs),
+            Self::RegexMatchExpr(expr) => write!(fmt, "{}=~{}", expr.lhs, expr.rhs),
+            Self::InExpr(expr) => write!(fmt, "{} IN {}", expr.lhs, expr.rhs),
             Self::AddExpr(expr) => write!(fmt, "{}+{}", expr.lhs, expr.rhs),
             Self::SubExpr(expr) => write!(fmt, "{}-{}", expr.lhs, expr.rhs),
             Self::MultExpr(expr) => write!(fmt, "{}*{}", expr.lhs, expr.rhs),
             Self::DivExpr(expr) => write!(fmt, "{}/{}", expr.lhs, expr.rhs),
+            Self::ModExpr(expr) => write!(fmt, "{}%{}", expr.lhs, expr.rhs),
             Self::PowerExpr(expr) => write!(fmt, "{}^{}", expr.lhs, expr.rhs),
             Self::UnarySubExpr(expr) => write!(fmt, "-{}", expr.child),
+            Self::IntegerLit(expr) => write!(fmt, "{}", expr),
             Self::NumberLit(expr) => write!(fmt, "{}", expr),
             Self::StringLit(expr) => write!(fmt, "{}", expr),
             Self::BooleanLit(expr) => write!(fmt, "{}", expr),
+            Self::NullLit => write!(fmt, "NULL"),
             Self::Variable(expr) => write!(fmt, "{}", expr),
+            Self::Parameter(expr) => write!(fmt, "${}", expr),
+            Self::FunctionCall(expr) => {
+                let args: Vec<String> = expr.args.iter().map(|v| v.to_string()).collect();
+                let distinct = if expr.distinct { "DISTINCT " } else { "" };
+                write!(fmt, "{}({}{})", expr.name, distinct, args.join(", "))
+            }
+            Self::CountStar => write!(fmt, "count(*)"),
+            Self::CaseExpr(expr) => {
+                write!(fmt, "CASE")?;
+                if let Some(operand) = expr.operand.as_ref() {
+                    write!(fmt, " {}", operand)?;
+                }
+                for (when, then) in expr.alternatives.iter() {
+                    write!(fmt, " WHEN {} THEN {}", when, then)?;
+                }
+                if let Some(default) = expr.default.as_ref() {
+                    write!(fmt, " ELSE {}", default)?;
+                }
+                write!(fmt, " END")
+            }
+            Self::ListLit(expr) => {
+                let elements: Vec<String> = expr.iter().map(|v| v.to_string()).collect();
+                write!(fmt, "[{}]", elements.join(", "))
+            }
+            Self::MapLit(expr) => {
+                let entries: Vec<String> =
+                    expr.iter().map(|v| format!("{}: {}", v.0, v.1)).collect();
+                write!(fmt, "{{{}}}", entries.join(", "))
+            }
             Self::PropertyLookup(expr) => write!(fmt, "{}.{}", expr.child, expr.prop_name),
+            Self::Subscript(expr) => write!(fmt, "{}[{}]", expr.child, expr.index),
+            Self::Slice(expr) => {
+                write!(fmt, "{}[", expr.child)?;
+                if let Some(from) = expr.from.as_ref() {
+                    write!(fmt, "{}", from)?;
+                }
+                write!(fmt, "..")?;
+                if let Some(to) = expr.to.as_ref() {
+                    write!(fmt, "{}", to)?;
+                }
+                write!(fmt, "]")
+            }
+            Self::MapProjection(expr) => {
+                let items: Vec<String> = expr
+                    .items
+                    .iter()
+                    .map(|v| match v {
+                        MapProjectionItem::Property(key) => format!(".{}", key),
+                        MapProjectionItem::AllProperties => String::from(".*"),
+                        MapProjectionItem::Literal(key, value) => format!("{}: {}", key, value),
+                        MapProjectionItem::Variable(name) => name.to_owned(),
+                    })
+                    .collect();
+                write!(fmt, "{}{{{}}}", expr.variable, items.join(", "))
+            }
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct EqualExpr {
     pub lhs: Box<Expr>,
     pub rhs: Box<Expr>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct NotEqualExpr {
     pub lhs: Box<Expr>,
     pub rhs: Box<Expr>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LessExpr {
     pub lhs: Box<Expr>,
     pub rhs: Box<Expr>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LessEqualExpr {
     pub lhs: Box<Expr>,
     pub rhs: Box<Expr>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct GreaterExpr {
     pub lhs: Box<Expr>,
     pub rhs: Box<Expr>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct GreaterEqualExpr {
     pub lhs: Box<Expr>,
     pub rhs: Box<Expr>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AndExpr {
     pub lhs: Box<Expr>,
     pub rhs: Box<Expr>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct OrExpr {
     pub lhs: Box<Expr>,
     pub rhs: Box<Expr>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct XorExpr {
     pub lhs: Box<Expr>,
     pub rhs: Box<Expr>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct NotExpr {
     pub child: Box<Expr>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub struct IsNullExpr {
+    pub child: Box<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct IsNotNullExpr {
+    pub child: Box<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StartsWithExpr {
+    pub lhs: Box<Expr>,
+    pub rhs: Box<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EndsWithExpr {
+    pub lhs: Box<Expr>,
+    pub rhs: Box<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ContainsExpr {
+    pub lhs: Box<Expr>,
+    pub rhs: Box<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RegexMatchExpr {
+    pub lhs: Box<Expr>,
+    pub rhs: Box<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct InExpr {
+    pub lhs: Box<Expr>,
+    pub rhs: Box<Expr>,
+}
+
+#[derive(Debug, Clone)]
 pub struct AddExpr {
     pub lhs: Box<Expr>,
     pub rhs: Box<Expr>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SubExpr {
     pub lhs: Box<Expr>,
     pub rhs: Box<Expr>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MultExpr {
     pub lhs: Box<Expr>,
     pub rhs: Box<Expr>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct DivExpr {
     pub lhs: Box<Expr>,
     pub rhs: Box<Expr>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub struct ModExpr {
+    pub lhs: Box<Expr>,
+    pub rhs: Box<Expr>,
+}
+
+#[derive(Debug, Clone)]
 pub struct PowerExpr {
     pub lhs: Box<Expr>,
     pub rhs: Box<Expr>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct UnarySubExpr {
     pub child: Box<Expr>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PropertyLookup {
     pub child: Box<Expr>,
     pub prop_name: String,
 }
+
+#[derive(Debug, Clone)]
+pub struct Subscript {
+    pub child: Box<Expr>,
+    pub index: Box<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Slice {
+    pub child: Box<Expr>,
+    pub from: Option<Box<Expr>>,
+    pub to: Option<Box<Expr>>,
+}
+
+// Map built from a variable, e.g. `n {.name, .*, friends: size(fs), age}`
+#[derive(Debug, Clone)]
+pub struct MapProjection {
+    pub variable: String,
+    pub items: Vec<MapProjectionItem>,
+}
+
+#[derive(Debug, Clone)]
+pub enum MapProjectionItem {
+    // `.key` selects a property of the variable
+    Property(String),
+    // `.*` selects all the properties of the variable
+    AllProperties,
+    // `key: expr`
+    Literal(String, Expr),
+    // `name` selects a variable with its name as the key
+    Variable(String),
+}
+
+// Simple CASE has operand to compare with, while generic CASE evaluates
+// conditions of alternatives
+#[derive(Debug, Clone)]
+pub struct CaseExpr {
+    pub operand: Option<Box<Expr>>,
+    pub alternatives: Vec<(Expr, Expr)>,
+    pub default: Option<Box<Expr>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FunctionCall {
+    // Name with namespace, e.g. `a.b.c`
+    pub name: String,
+    // Only allowed in aggregation functions
+    pub distinct: bool,
+    pub args: Vec<Expr>,
+}
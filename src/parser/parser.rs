@@ -1,15 +1,13 @@
-use core::f64;
-
 use super::expr::*;
 use super::stmt::*;
 use nom::{
     branch::alt,
     bytes::complete::{tag, tag_no_case, take_while, take_while1},
     character::complete::{char as c, digit1, hex_digit1, multispace0, multispace1, one_of},
-    combinator::{map, opt},
-    multi::many0,
+    combinator::{eof, map, map_res, not, opt, recognize, value, verify},
+    multi::{many0, many1},
     number::complete::double,
-    sequence::{delimited, pair, terminated, tuple},
+    sequence::{delimited, pair, preceded, terminated, tuple},
     IResult,
 };
 
@@ -29,32 +27,127 @@ impl Parser {
         })
     }
 
+    // Whole input must be a query, which may end with `;`
     fn parse_impl(input: &str) -> IResult<&str, Query> {
-        map(terminated(tuple((sp0, query, sp0)), opt(tag(";"))), |v| v.1)(input)
+        map(
+            terminated(tuple((sp0, query, sp0)), tuple((opt(tag(";")), sp0, eof))),
+            |v| v.1,
+        )(input)
     }
 }
 
 fn query(input: &str) -> IResult<&str, Query> {
-    map(tuple((reading_clause, sp0, return_clause)), |v| Query {
-        unions: Vec::from([Union {
-            reading_clause: Some(v.0),
-            updating_clauses: Vec::new(),
-            return_clause: v.2,
-        }]),
-        sort_items: Vec::new(),
-        ordering: None,
-        skip: None,
-        limit: None,
-    })(input)
+    map(
+        tuple((
+            single_query,
+            many0(tuple((
+                sp0,
+                tag_no_case("UNION"),
+                opt(preceded(sp1, tag_no_case("ALL"))),
+                sp0,
+                single_query,
+            ))),
+        )),
+        |v| {
+            let mut unions = Vec::from([v.0]);
+            for (_, _, all, _, mut union) in v.1.into_iter() {
+                union.all = all.is_some();
+                unions.push(union);
+            }
+            Query { unions: unions }
+        },
+    )(input)
+}
+
+fn single_query(input: &str) -> IResult<&str, Union> {
+    map(
+        tuple((many0(terminated(query_part, sp0)), single_part_query)),
+        |v| {
+            let mut union = v.1;
+            union.parts = v.0;
+            union
+        },
+    )(input)
+}
+
+fn query_part(input: &str) -> IResult<&str, QueryPart> {
+    map(
+        tuple((
+            many0(terminated(reading_clause, sp0)),
+            many0(terminated(updating_clause, sp0)),
+            with_clause,
+        )),
+        |v| QueryPart {
+            reading_clauses: v.0,
+            updating_clauses: v.1,
+            with_clause: v.2,
+        },
+    )(input)
+}
+
+fn single_part_query(input: &str) -> IResult<&str, Union> {
+    alt((
+        map(
+            tuple((
+                many0(terminated(reading_clause, sp0)),
+                updating_clause,
+                many0(preceded(sp0, updating_clause)),
+                opt(preceded(sp0, return_clause)),
+            )),
+            |v| {
+                let mut updating_clauses = Vec::new();
+                updating_clauses.push(v.1);
+                updating_clauses.extend(v.2.into_iter());
+                Union {
+                    all: false,
+                    parts: Vec::new(),
+                    reading_clauses: v.0,
+                    updating_clauses: updating_clauses,
+                    return_clause: v.3,
+                }
+            },
+        ),
+        map(
+            tuple((many0(terminated(reading_clause, sp0)), return_clause)),
+            |v| Union {
+                all: false,
+                parts: Vec::new(),
+                reading_clauses: v.0,
+                updating_clauses: Vec::new(),
+                return_clause: Some(v.1),
+            },
+        ),
+    ))(input)
 }
 
 fn reading_clause(input: &str) -> IResult<&str, ReadingClause> {
-    match_clause(input)
+    alt((match_clause, unwind_clause))(input)
+}
+
+fn unwind_clause(input: &str) -> IResult<&str, ReadingClause> {
+    map(
+        tuple((
+            tag_no_case("UNWIND"),
+            sp1,
+            expr,
+            sp1,
+            tag_no_case("AS"),
+            sp1,
+            symbolic_name,
+        )),
+        |v| {
+            ReadingClause::Unwind(UnwindClause {
+                expr: v.2,
+                name: v.6,
+            })
+        },
+    )(input)
 }
 
 fn match_clause(input: &str) -> IResult<&str, ReadingClause> {
     map(
         tuple((
+            opt(tuple((tag_no_case("OPTIONAL"), sp1))),
             tag_no_case("MATCH"),
             sp1,
             pattern,
@@ -63,16 +156,17 @@ fn match_clause(input: &str) -> IResult<&str, ReadingClause> {
         )),
         |v| {
             let mut patterns = Vec::new();
-            patterns.push(v.2);
-            for i in v.3.into_iter() {
+            patterns.push(v.3);
+            for i in v.4.into_iter() {
                 patterns.push(i.2);
             }
             let mut predicate = Vec::new();
-            match v.4 {
+            match v.5 {
                 Some((_, _, _, e)) => predicate.push(e),
                 None => {}
             };
             ReadingClause::Match(MatchClause {
+                optional: v.0.is_some(),
                 pattern: patterns,
                 filter: predicate,
             })
@@ -80,53 +174,152 @@ fn match_clause(input: &str) -> IResult<&str, ReadingClause> {
     )(input)
 }
 
+fn with_clause(input: &str) -> IResult<&str, WithClause> {
+    map(
+        tuple((
+            tag_no_case("WITH"),
+            sp1,
+            projection_body,
+            opt(tuple((sp1, tag_no_case("WHERE"), sp1, expr))),
+        )),
+        |v| {
+            let (star, projections, sort_items, skip, limit) = v.2;
+            let mut predicate = Vec::new();
+            match v.3 {
+                Some((_, _, _, e)) => predicate.push(e),
+                None => {}
+            };
+            WithClause {
+                star: star,
+                projections: projections,
+                sort_items: sort_items,
+                skip: skip,
+                limit: limit,
+                filter: predicate,
+            }
+        },
+    )(input)
+}
+
 fn return_clause(input: &str) -> IResult<&str, ReturnClause> {
+    map(tuple((tag_no_case("RETURN"), sp1, projection_body)), |v| {
+        let (star, projections, sort_items, skip, limit) = v.2;
+        ReturnClause {
+            star: star,
+            projections: projections,
+            sort_items: sort_items,
+            skip: skip,
+            limit: limit,
+        }
+    })(input)
+}
+
+fn projection_body(
+    input: &str,
+) -> IResult<
+    &str,
+    (
+        bool,
+        Vec<(Expr, String)>,
+        Vec<SortItem>,
+        Option<Expr>,
+        Option<Expr>,
+    ),
+> {
+    map(
+        tuple((
+            projection_items,
+            opt(preceded(sp1, order)),
+            opt(preceded(sp1, skip)),
+            opt(preceded(sp1, limit)),
+        )),
+        |v| (v.0 .0, v.0 .1, v.1.unwrap_or_default(), v.2, v.3),
+    )(input)
+}
+
+fn order(input: &str) -> IResult<&str, Vec<SortItem>> {
     map(
         tuple((
-            tag_no_case("RETURN"),
+            tag_no_case("ORDER"),
             sp1,
-            alt((
-                map(
-                    tuple((
-                        tag("*"),
-                        many0(tuple((sp0, tag(","), sp0, projection_item))),
-                    )),
-                    |v| ReturnClause {
-                        star: true,
-                        projections: v.1.into_iter().map(|v| v.3).collect(),
-                    },
-                ),
-                map(
-                    tuple((
-                        projection_item,
-                        many0(tuple((sp0, tag(","), sp0, projection_item))),
-                    )),
-                    |v| {
-                        let mut projections = Vec::new();
-                        projections.push(v.0);
-                        v.1.into_iter().for_each(|v| projections.push(v.3));
-                        ReturnClause {
-                            star: false,
-                            projections: projections,
-                        }
-                    },
-                ),
+            tag_no_case("BY"),
+            sp1,
+            sort_item,
+            many0(tuple((sp0, c(','), sp0, sort_item))),
+        )),
+        |v| {
+            let mut items = Vec::new();
+            items.push(v.4);
+            v.5.into_iter().for_each(|v| items.push(v.3));
+            items
+        },
+    )(input)
+}
+
+fn sort_item(input: &str) -> IResult<&str, SortItem> {
+    map(
+        tuple((
+            expr,
+            opt(preceded(
+                sp1,
+                alt((
+                    map(tag_no_case("ASCENDING"), |_| SortOrdering::Ascending),
+                    map(tag_no_case("ASC"), |_| SortOrdering::Ascending),
+                    map(tag_no_case("DESCENDING"), |_| SortOrdering::Descending),
+                    map(tag_no_case("DESC"), |_| SortOrdering::Descending),
+                )),
             )),
         )),
-        |v| v.2,
+        |v| SortItem {
+            expr: v.0,
+            ordering: v.1.unwrap_or(SortOrdering::Ascending),
+        },
     )(input)
 }
 
+fn skip(input: &str) -> IResult<&str, Expr> {
+    map(tuple((tag_no_case("SKIP"), sp1, expr)), |v| v.2)(input)
+}
+
+fn limit(input: &str) -> IResult<&str, Expr> {
+    map(tuple((tag_no_case("LIMIT"), sp1, expr)), |v| v.2)(input)
+}
+
+// Returns whether there is a `*` and the projection items
+fn projection_items(input: &str) -> IResult<&str, (bool, Vec<(Expr, String)>)> {
+    alt((
+        map(
+            tuple((
+                tag("*"),
+                many0(tuple((sp0, tag(","), sp0, projection_item))),
+            )),
+            |v| (true, v.1.into_iter().map(|v| v.3).collect()),
+        ),
+        map(
+            tuple((
+                projection_item,
+                many0(tuple((sp0, tag(","), sp0, projection_item))),
+            )),
+            |v| {
+                let mut projections = Vec::new();
+                projections.push(v.0);
+                v.1.into_iter().for_each(|v| projections.push(v.3));
+                (false, projections)
+            },
+        ),
+    ))(input)
+}
+
 fn projection_item(input: &str) -> IResult<&str, (Expr, String)> {
     alt((
+        map(
+            tuple((expr, sp1, tag_no_case("AS"), sp1, symbolic_name)),
+            |v| (v.0, v.4),
+        ),
         map(expr, |v| {
             let s = format!("{}", &v);
             (v, s)
         }),
-        map(
-            tuple((expr, sp0, tag_no_case("AS"), sp0, symbolic_name)),
-            |v| (v.0, v.4),
-        ),
     ))(input)
 }
 
@@ -156,286 +349,961 @@ fn node_pattern(input: &str) -> IResult<&str, NodePattern> {
         tuple((
             c('('),
             sp0,
-            symbolic_name,
+            opt(symbolic_name),
             sp0,                                   // Variable
             many0(tuple((c(':'), symbolic_name))), // labels
             sp0,
+            opt(terminated(properties, sp0)), // properties
             c(')'),
         )),
         |v| NodePattern {
             name: v.2,
             labels: v.4.into_iter().map(|v| v.1).collect(),
+            properties: v.6.unwrap_or_default(),
         },
     )(input)
 }
 
 fn relationship_pattern(input: &str) -> IResult<&str, RelationshipPattern> {
-    alt((
-        map(
-            tuple((
-                left_arrow,
-                sp0,
-                dash,
-                sp0,
-                c('['),
-                symbolic_name,
-                sp0,
-                many0(tuple((c(':'), symbolic_name))),
-                sp0,
-                c(']'),
-                sp0,
-                dash,
-                sp0,
-            )),
-            |v| RelationshipPattern {
-                name: v.5,
-                direction: Direction::Left,
-                types: v.7.into_iter().map(|v| v.1).collect(),
-            },
-        ),
-        map(
-            tuple((
-                sp0,
-                dash,
-                sp0,
-                c('['),
-                symbolic_name,
-                sp0,
-                many0(tuple((c(':'), symbolic_name))),
-                sp0,
-                c(']'),
-                sp0,
-                dash,
-                sp0,
-                right_arrow,
-            )),
-            |v| RelationshipPattern {
-                name: v.4,
-                direction: Direction::Right,
-                types: v.6.into_iter().map(|v| v.1).collect(),
+    map(
+        alt((
+            map(
+                tuple((
+                    left_arrow,
+                    sp0,
+                    dash,
+                    sp0,
+                    opt(relationship_detail),
+                    sp0,
+                    dash,
+                    sp0,
+                )),
+                |v| (Direction::Left, v.4),
+            ),
+            map(
+                tuple((
+                    sp0,
+                    dash,
+                    sp0,
+                    opt(relationship_detail),
+                    sp0,
+                    dash,
+                    sp0,
+                    right_arrow,
+                )),
+                |v| (Direction::Right, v.3),
+            ),
+            map(
+                tuple((sp0, dash, sp0, opt(relationship_detail), sp0, dash, sp0)),
+                |v| (Direction::Both, v.3),
+            ),
+        )),
+        |(direction, detail)| match detail {
+            Some(mut rel) => {
+                rel.direction = direction;
+                rel
+            }
+            // Anonymous relationship without brackets, e.g. `-->`
+            None => RelationshipPattern {
+                name: None,
+                direction: direction,
+                types: Vec::new(),
+                length: None,
+                properties: Vec::new(),
             },
-        ),
-    ))(input)
-}
-
-fn updating_clause(input: &str) -> IResult<&str, UpdatingClause> {
-    unimplemented!()
-}
-
-fn expr(input: &str) -> IResult<&str, Expr> {
-    or_expr(input)
+        },
+    )(input)
 }
 
-fn or_expr(input: &str) -> IResult<&str, Expr> {
+// Bracketed part of relationship pattern, e.g. `[r:knows*1..2 {since: 2020}]`
+fn relationship_detail(input: &str) -> IResult<&str, RelationshipPattern> {
     map(
         tuple((
-            xor_expr,
-            opt(tuple((sp1, tag_no_case("OR"), sp1, xor_expr))),
+            c('['),
+            sp0,
+            opt(symbolic_name),
+            sp0,
+            opt(terminated(relationship_types, sp0)),
+            opt(terminated(range_literal, sp0)),
+            opt(terminated(properties, sp0)),
+            c(']'),
         )),
-        |v| match v.1 {
-            Some(e) => Expr::OrExpr(OrExpr {
-                lhs: Box::new(v.0),
-                rhs: Box::new(e.3),
-            }),
-            None => v.0,
+        |v| RelationshipPattern {
+            name: v.2,
+            direction: Direction::Both,
+            types: v.4.unwrap_or_default(),
+            length: v.5,
+            properties: v.6.unwrap_or_default(),
         },
     )(input)
 }
 
-fn xor_expr(input: &str) -> IResult<&str, Expr> {
+// Relationship matches any of the types, e.g. `:KNOWS|LIKES` or `:KNOWS|:LIKES`
+fn relationship_types(input: &str) -> IResult<&str, Vec<String>> {
     map(
         tuple((
-            and_expr,
-            opt(tuple((sp1, tag_no_case("XOR"), sp1, and_expr))),
+            c(':'),
+            sp0,
+            symbolic_name,
+            many0(preceded(
+                tuple((sp0, c('|'), sp0, opt(c(':')), sp0)),
+                symbolic_name,
+            )),
         )),
-        |v| match v.1 {
-            Some(e) => Expr::XorExpr(XorExpr {
-                lhs: Box::new(v.0),
-                rhs: Box::new(e.3),
-            }),
-            None => v.0,
+        |v| {
+            let mut types = vec![v.2];
+            types.extend(v.3);
+            types
         },
     )(input)
 }
 
-fn and_expr(input: &str) -> IResult<&str, Expr> {
+// Hops of variable length relationship, `*n` is a fixed length while bounds
+// of `*n..m` can be omitted
+fn range_literal(input: &str) -> IResult<&str, PathLength> {
     map(
         tuple((
-            not_expr,
-            opt(tuple((sp1, tag_no_case("AND"), sp1, not_expr))),
+            c('*'),
+            sp0,
+            opt(terminated(integer_literal, sp0)),
+            opt(preceded(tag(".."), preceded(sp0, opt(integer_literal)))),
         )),
-        |v| match v.1 {
-            Some(e) => Expr::AndExpr(AndExpr {
-                lhs: Box::new(v.0),
-                rhs: Box::new(e.3),
-            }),
-            None => v.0,
+        |v| match v.3 {
+            Some(max) => PathLength { min: v.2, max: max },
+            None => PathLength { min: v.2, max: v.2 },
         },
     )(input)
 }
 
-fn not_expr(input: &str) -> IResult<&str, Expr> {
-    map(
-        tuple((opt(pair(tag_no_case("NOT"), sp1)), comp_expr)),
-        |v| match v.0 {
-            Some(_) => Expr::NotExpr(NotExpr {
-                child: Box::new(v.1),
-            }),
-            None => v.1,
-        },
-    )(input)
+fn integer_literal(input: &str) -> IResult<&str, u64> {
+    map_res(digit1, |v: &str| v.parse::<u64>())(input)
 }
 
-fn comp_expr(input: &str) -> IResult<&str, Expr> {
+// Properties of node or relationship, e.g. `{name: 'Alice', age: 18}`
+fn properties(input: &str) -> IResult<&str, Vec<(String, Expr)>> {
     map(
-        tuple((add_sub_expr, opt(pair(sp1, partial_comp_expr)))),
-        |v| match v.1 {
-            Some(e) => match e.1 {
-                ("=", r) => Expr::EqualExpr(EqualExpr {
-                    lhs: Box::new(v.0),
-                    rhs: Box::new(r),
-                }),
-                ("!=", r) => Expr::NotEqualExpr(NotEqualExpr {
-                    lhs: Box::new(v.0),
-                    rhs: Box::new(r),
-                }),
-                ("<", r) => Expr::LessExpr(LessExpr {
-                    lhs: Box::new(v.0),
-                    rhs: Box::new(r),
-                }),
-                ("<=", r) => Expr::LessEqualExpr(LessEqualExpr {
-                    lhs: Box::new(v.0),
-                    rhs: Box::new(r),
-                }),
-                (">", r) => Expr::GreaterExpr(GreaterExpr {
-                    lhs: Box::new(v.0),
-                    rhs: Box::new(r),
-                }),
-                (">=", r) => Expr::GreaterEqualExpr(GreaterEqualExpr {
-                    lhs: Box::new(v.0),
-                    rhs: Box::new(r),
-                }),
-                _ => panic!("unexpected"),
-            },
-            None => v.0,
+        tuple((
+            c('{'),
+            sp0,
+            opt(tuple((
+                property_key_value,
+                many0(tuple((sp0, c(','), sp0, property_key_value))),
+            ))),
+            sp0,
+            c('}'),
+        )),
+        |v| {
+            let mut props = Vec::new();
+            if let Some((first, rest)) = v.2 {
+                props.push(first);
+                rest.into_iter().for_each(|v| props.push(v.3));
+            }
+            props
         },
     )(input)
 }
 
-fn partial_comp_expr(input: &str) -> IResult<&str, (&str, Expr)> {
+fn property_key_value(input: &str) -> IResult<&str, (String, Expr)> {
+    map(tuple((symbolic_name, sp0, c(':'), sp0, expr)), |v| {
+        (v.0, v.4)
+    })(input)
+}
+
+fn updating_clause(input: &str) -> IResult<&str, UpdatingClause> {
+    alt((
+        create_clause,
+        merge_clause,
+        delete_clause,
+        set_clause,
+        remove_clause,
+    ))(input)
+}
+
+fn create_clause(input: &str) -> IResult<&str, UpdatingClause> {
     map(
         tuple((
-            alt((tag("="), tag("<"), tag("<="), tag(">"), tag(">="))),
+            tag_no_case("CREATE"),
             sp0,
-            add_sub_expr,
+            pattern,
+            many0(tuple((sp0, tag(","), sp0, pattern))),
         )),
-        |v| (v.0, v.2),
+        |v| {
+            let mut patterns = Vec::new();
+            patterns.push(v.2);
+            for i in v.3.into_iter() {
+                patterns.push(i.3);
+            }
+            UpdatingClause::Create(CreateClause { pattern: patterns })
+        },
     )(input)
 }
 
-fn add_sub_expr(input: &str) -> IResult<&str, Expr> {
+fn delete_clause(input: &str) -> IResult<&str, UpdatingClause> {
     map(
         tuple((
-            mul_div_expr,
-            opt(tuple((sp1, one_of("+-"), sp1, mul_div_expr))),
+            opt(pair(tag_no_case("DETACH"), sp1)),
+            tag_no_case("DELETE"),
+            sp0,
+            expr,
+            many0(tuple((sp0, c(','), sp0, expr))),
         )),
-        |v| match v.1 {
-            Some(e) => match e.1 {
-                '+' => Expr::AddExpr(AddExpr {
-                    lhs: Box::new(v.0),
-                    rhs: Box::new(e.3),
-                }),
-                '-' => Expr::SubExpr(SubExpr {
-                    lhs: Box::new(v.0),
-                    rhs: Box::new(e.3),
-                }),
-                _ => panic!("unexpected"),
-            },
-            None => v.0,
+        |v| {
+            let mut exprs = Vec::new();
+            exprs.push(v.3);
+            v.4.into_iter().for_each(|v| exprs.push(v.3));
+            UpdatingClause::Delete(DeleteClause {
+                detach: v.0.is_some(),
+                exprs: exprs,
+            })
         },
     )(input)
 }
 
-fn mul_div_expr(input: &str) -> IResult<&str, Expr> {
+fn merge_clause(input: &str) -> IResult<&str, UpdatingClause> {
     map(
-        tuple((power_expr, opt(tuple((sp1, one_of("*/"), sp1, power_expr))))),
-        |v| match v.1 {
-            Some(e) => match e.1 {
-                '*' => Expr::MultExpr(MultExpr {
-                    lhs: Box::new(v.0),
-                    rhs: Box::new(e.3),
-                }),
-                '/' => Expr::DivExpr(DivExpr {
-                    lhs: Box::new(v.0),
-                    rhs: Box::new(e.3),
-                }),
-                _ => panic!("unexpected"),
-            },
-            None => v.0,
+        tuple((
+            tag_no_case("MERGE"),
+            sp0,
+            pattern,
+            many0(preceded(sp1, merge_action)),
+        )),
+        |v| {
+            let mut on_match = Vec::new();
+            let mut on_create = Vec::new();
+            for (is_create, items) in v.3.into_iter() {
+                if is_create {
+                    on_create.extend(items);
+                } else {
+                    on_match.extend(items);
+                }
+            }
+            UpdatingClause::Merge(MergeClause {
+                pattern: v.2,
+                on_match: on_match,
+                on_create: on_create,
+            })
         },
     )(input)
 }
 
-fn power_expr(input: &str) -> IResult<&str, Expr> {
+// `ON MATCH SET ...` or `ON CREATE SET ...`, returns true if it's `ON CREATE`
+fn merge_action(input: &str) -> IResult<&str, (bool, Vec<SetItem>)> {
     map(
         tuple((
-            unary_add_sub_expr,
-            opt(tuple((sp1, tag("^"), sp1, unary_add_sub_expr))),
+            tag_no_case("ON"),
+            sp1,
+            alt((
+                map(tag_no_case("MATCH"), |_| false),
+                map(tag_no_case("CREATE"), |_| true),
+            )),
+            sp1,
+            set_items,
         )),
-        |v| match v.1 {
-            Some(e) => Expr::PowerExpr(PowerExpr {
-                lhs: Box::new(v.0),
-                rhs: Box::new(e.3),
-            }),
-            None => v.0,
-        },
+        |v| (v.2, v.4),
     )(input)
 }
 
-fn unary_add_sub_expr(input: &str) -> IResult<&str, Expr> {
-    map(
-        tuple((opt(tuple((one_of("+-"), sp0))), property_lookup_expr)),
-        |v| match v.0 {
-            Some(e) => Expr::UnarySubExpr(UnarySubExpr {
-                child: Box::new(v.1),
-            }),
-            None => v.1,
-        },
-    )(input)
+fn set_clause(input: &str) -> IResult<&str, UpdatingClause> {
+    map(set_items, |v| UpdatingClause::Set(SetClause { items: v }))(input)
 }
 
-fn property_lookup_expr(input: &str) -> IResult<&str, Expr> {
+fn set_items(input: &str) -> IResult<&str, Vec<SetItem>> {
     map(
-        tuple((atom_expr, opt(tuple((sp0, tag("."), sp0, symbolic_name))))),
-        |v| match v.1 {
-            Some(e) => Expr::PropertyLookup(PropertyLookup {
-                child: Box::new(v.0),
-                prop_name: e.3,
-            }),
-            None => v.0,
+        tuple((
+            tag_no_case("SET"),
+            sp0,
+            set_item,
+            many0(tuple((sp0, c(','), sp0, set_item))),
+        )),
+        |v| {
+            let mut items = Vec::new();
+            items.push(v.2);
+            v.3.into_iter().for_each(|v| items.push(v.3));
+            items
         },
     )(input)
 }
 
-fn atom_expr(input: &str) -> IResult<&str, Expr> {
-    alt((literal, map(symbolic_name, |v| Expr::Variable(v))))(input)
-}
-
-fn literal(input: &str) -> IResult<&str, Expr> {
-    alt((number_literal, string_literal, boolean_literal))(input)
-}
-
-fn number_literal(input: &str) -> IResult<&str, Expr> {
+fn set_item(input: &str) -> IResult<&str, SetItem> {
     alt((
-        map(double, |v| Expr::NumberLit(v)),
-        map(pair(tag_no_case("0X"), hex_digit1), |v| {
-            Expr::NumberLit(i64::from_str_radix(v.1, 16).unwrap() as f64)
-        }),
-        map(digit1, |v| {
-            Expr::NumberLit(i64::from_str_radix(v, 10).unwrap() as f64)
-        }),
-    ))(input)
-}
+        map(
+            tuple((
+                symbolic_name,
+                sp0,
+                c('.'),
+                sp0,
+                symbolic_name,
+                sp0,
+                c('='),
+                sp0,
+                expr,
+            )),
+            |v| SetItem::Property {
+                variable: v.0,
+                key: v.4,
+                value: v.8,
+            },
+        ),
+        map(
+            tuple((symbolic_name, sp0, tag("+="), sp0, properties)),
+            |v| SetItem::Append {
+                variable: v.0,
+                properties: v.4,
+            },
+        ),
+        map(tuple((symbolic_name, sp0, c('='), sp0, properties)), |v| {
+            SetItem::Replace {
+                variable: v.0,
+                properties: v.4,
+            }
+        }),
+        map(tuple((symbolic_name, sp0, node_labels)), |v| {
+            SetItem::Labels {
+                variable: v.0,
+                labels: v.2,
+            }
+        }),
+    ))(input)
+}
+
+fn remove_clause(input: &str) -> IResult<&str, UpdatingClause> {
+    map(
+        tuple((
+            tag_no_case("REMOVE"),
+            sp1,
+            remove_item,
+            many0(tuple((sp0, c(','), sp0, remove_item))),
+        )),
+        |v| {
+            let mut items = Vec::new();
+            items.push(v.2);
+            v.3.into_iter().for_each(|v| items.push(v.3));
+            UpdatingClause::Remove(RemoveClause { items: items })
+        },
+    )(input)
+}
+
+fn remove_item(input: &str) -> IResult<&str, RemoveItem> {
+    alt((
+        map(
+            tuple((symbolic_name, sp0, c('.'), sp0, symbolic_name)),
+            |v| RemoveItem::Property {
+                variable: v.0,
+                key: v.4,
+            },
+        ),
+        map(tuple((symbolic_name, sp0, node_labels)), |v| {
+            RemoveItem::Labels {
+                variable: v.0,
+                labels: v.2,
+            }
+        }),
+    ))(input)
+}
+
+fn node_labels(input: &str) -> IResult<&str, Vec<String>> {
+    map(many1(tuple((sp0, c(':'), sp0, symbolic_name))), |v| {
+        v.into_iter().map(|v| v.3).collect()
+    })(input)
+}
+
+fn expr(input: &str) -> IResult<&str, Expr> {
+    or_expr(input)
+}
+
+// Binary operators of the same precedence are left associative, so `a - b - c`
+// is `(a - b) - c`
+fn or_expr(input: &str) -> IResult<&str, Expr> {
+    map(
+        tuple((
+            xor_expr,
+            many0(preceded(tuple((sp1, tag_no_case("OR"), sp1)), xor_expr)),
+        )),
+        |v| {
+            v.1.into_iter().fold(v.0, |lhs, rhs| {
+                Expr::OrExpr(OrExpr {
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                })
+            })
+        },
+    )(input)
+}
+
+fn xor_expr(input: &str) -> IResult<&str, Expr> {
+    map(
+        tuple((
+            and_expr,
+            many0(preceded(tuple((sp1, tag_no_case("XOR"), sp1)), and_expr)),
+        )),
+        |v| {
+            v.1.into_iter().fold(v.0, |lhs, rhs| {
+                Expr::XorExpr(XorExpr {
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                })
+            })
+        },
+    )(input)
+}
+
+fn and_expr(input: &str) -> IResult<&str, Expr> {
+    map(
+        tuple((
+            not_expr,
+            many0(preceded(tuple((sp1, tag_no_case("AND"), sp1)), not_expr)),
+        )),
+        |v| {
+            v.1.into_iter().fold(v.0, |lhs, rhs| {
+                Expr::AndExpr(AndExpr {
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                })
+            })
+        },
+    )(input)
+}
+
+fn not_expr(input: &str) -> IResult<&str, Expr> {
+    map(
+        tuple((
+            // NOT must not be the prefix of a name, e.g. `note`
+            many0(tuple((tag_no_case("NOT"), not(symbolic_name), sp0))),
+            comp_expr,
+        )),
+        |v| {
+            v.0.into_iter().fold(v.1, |child, _| {
+                Expr::NotExpr(NotExpr {
+                    child: Box::new(child),
+                })
+            })
+        },
+    )(input)
+}
+
+// Chained comparison `a < b <= c` is equivalent to `a < b AND b <= c`
+fn comp_expr(input: &str) -> IResult<&str, Expr> {
+    map(
+        tuple((
+            string_list_null_predicate_expr,
+            many0(preceded(sp0, partial_comp_expr)),
+        )),
+        |v| {
+            let mut lhs = v.0;
+            let mut result = None;
+            for (op, rhs) in v.1.into_iter() {
+                let comparison = comparison(op, lhs, rhs.clone());
+                result = match result {
+                    Some(result) => Some(Expr::AndExpr(AndExpr {
+                        lhs: Box::new(result),
+                        rhs: Box::new(comparison),
+                    })),
+                    None => Some(comparison),
+                };
+                lhs = rhs;
+            }
+            result.unwrap_or(lhs)
+        },
+    )(input)
+}
+
+#[derive(Clone, Copy)]
+enum ComparisonOperator {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+fn comparison(op: ComparisonOperator, lhs: Expr, rhs: Expr) -> Expr {
+    let (lhs, rhs) = (Box::new(lhs), Box::new(rhs));
+    match op {
+        ComparisonOperator::Equal => Expr::EqualExpr(EqualExpr { lhs: lhs, rhs: rhs }),
+        ComparisonOperator::NotEqual => Expr::NotEqualExpr(NotEqualExpr { lhs: lhs, rhs: rhs }),
+        ComparisonOperator::Less => Expr::LessExpr(LessExpr { lhs: lhs, rhs: rhs }),
+        ComparisonOperator::LessEqual => Expr::LessEqualExpr(LessEqualExpr { lhs: lhs, rhs: rhs }),
+        ComparisonOperator::Greater => Expr::GreaterExpr(GreaterExpr { lhs: lhs, rhs: rhs }),
+        ComparisonOperator::GreaterEqual => {
+            Expr::GreaterEqualExpr(GreaterEqualExpr { lhs: lhs, rhs: rhs })
+        }
+    }
+}
+
+fn partial_comp_expr(input: &str) -> IResult<&str, (ComparisonOperator, Expr)> {
+    map(
+        tuple((
+            // Longer operators are tried first
+            alt((
+                value(ComparisonOperator::NotEqual, alt((tag("<>"), tag("!=")))),
+                value(ComparisonOperator::LessEqual, tag("<=")),
+                value(ComparisonOperator::GreaterEqual, tag(">=")),
+                value(ComparisonOperator::Equal, tag("=")),
+                value(ComparisonOperator::Less, tag("<")),
+                value(ComparisonOperator::Greater, tag(">")),
+            )),
+            sp0,
+            string_list_null_predicate_expr,
+        )),
+        |v| (v.0, v.2),
+    )(input)
+}
+
+enum PredicateOperator {
+    IsNull,
+    IsNotNull,
+    StartsWith(Expr),
+    EndsWith(Expr),
+    Contains(Expr),
+    RegexMatch(Expr),
+    In(Expr),
+}
+
+fn string_list_null_predicate_expr(input: &str) -> IResult<&str, Expr> {
+    map(
+        tuple((
+            add_sub_expr,
+            many0(alt((
+                preceded(sp1, predicate_operator),
+                map(tuple((sp0, tag("=~"), sp0, add_sub_expr)), |v| {
+                    PredicateOperator::RegexMatch(v.3)
+                }),
+            ))),
+        )),
+        |v| {
+            let mut expr = v.0;
+            for op in v.1.into_iter() {
+                expr = match op {
+                    PredicateOperator::IsNull => Expr::IsNullExpr(IsNullExpr {
+                        child: Box::new(expr),
+                    }),
+                    PredicateOperator::IsNotNull => Expr::IsNotNullExpr(IsNotNullExpr {
+                        child: Box::new(expr),
+                    }),
+                    PredicateOperator::StartsWith(rhs) => Expr::StartsWithExpr(StartsWithExpr {
+                        lhs: Box::new(expr),
+                        rhs: Box::new(rhs),
+                    }),
+                    PredicateOperator::EndsWith(rhs) => Expr::EndsWithExpr(EndsWithExpr {
+                        lhs: Box::new(expr),
+                        rhs: Box::new(rhs),
+                    }),
+                    PredicateOperator::Contains(rhs) => Expr::ContainsExpr(ContainsExpr {
+                        lhs: Box::new(expr),
+                        rhs: Box::new(rhs),
+                    }),
+                    PredicateOperator::RegexMatch(rhs) => Expr::RegexMatchExpr(RegexMatchExpr {
+                        lhs: Box::new(expr),
+                        rhs: Box::new(rhs),
+                    }),
+                    PredicateOperator::In(rhs) => Expr::InExpr(InExpr {
+                        lhs: Box::new(expr),
+                        rhs: Box::new(rhs),
+                    }),
+                };
+            }
+            expr
+        },
+    )(input)
+}
+
+fn predicate_operator(input: &str) -> IResult<&str, PredicateOperator> {
+    alt((
+        map(tuple((tag_no_case("IS"), sp1, tag_no_case("NULL"))), |_| {
+            PredicateOperator::IsNull
+        }),
+        map(
+            tuple((
+                tag_no_case("IS"),
+                sp1,
+                tag_no_case("NOT"),
+                sp1,
+                tag_no_case("NULL"),
+            )),
+            |_| PredicateOperator::IsNotNull,
+        ),
+        map(
+            tuple((
+                tag_no_case("STARTS"),
+                sp1,
+                tag_no_case("WITH"),
+                sp1,
+                add_sub_expr,
+            )),
+            |v| PredicateOperator::StartsWith(v.4),
+        ),
+        map(
+            tuple((
+                tag_no_case("ENDS"),
+                sp1,
+                tag_no_case("WITH"),
+                sp1,
+                add_sub_expr,
+            )),
+            |v| PredicateOperator::EndsWith(v.4),
+        ),
+        map(tuple((tag_no_case("CONTAINS"), sp1, add_sub_expr)), |v| {
+            PredicateOperator::Contains(v.2)
+        }),
+        map(tuple((tag_no_case("IN"), sp0, add_sub_expr)), |v| {
+            PredicateOperator::In(v.2)
+        }),
+    ))(input)
+}
+
+#[derive(Clone, Copy)]
+enum ArithmeticOperator {
+    Add,
+    Sub,
+    Mult,
+    Div,
+    Mod,
+}
+
+fn arithmetic(op: ArithmeticOperator, lhs: Expr, rhs: Expr) -> Expr {
+    let (lhs, rhs) = (Box::new(lhs), Box::new(rhs));
+    match op {
+        ArithmeticOperator::Add => Expr::AddExpr(AddExpr { lhs: lhs, rhs: rhs }),
+        ArithmeticOperator::Sub => Expr::SubExpr(SubExpr { lhs: lhs, rhs: rhs }),
+        ArithmeticOperator::Mult => Expr::MultExpr(MultExpr { lhs: lhs, rhs: rhs }),
+        ArithmeticOperator::Div => Expr::DivExpr(DivExpr { lhs: lhs, rhs: rhs }),
+        ArithmeticOperator::Mod => Expr::ModExpr(ModExpr { lhs: lhs, rhs: rhs }),
+    }
+}
+
+fn add_sub_expr(input: &str) -> IResult<&str, Expr> {
+    map(
+        tuple((
+            mul_div_expr,
+            many0(tuple((
+                sp0,
+                alt((
+                    value(ArithmeticOperator::Add, c('+')),
+                    value(ArithmeticOperator::Sub, c('-')),
+                )),
+                sp0,
+                mul_div_expr,
+            ))),
+        )),
+        |v| {
+            v.1.into_iter()
+                .fold(v.0, |lhs, e| arithmetic(e.1, lhs, e.3))
+        },
+    )(input)
+}
+
+fn mul_div_expr(input: &str) -> IResult<&str, Expr> {
+    map(
+        tuple((
+            power_expr,
+            many0(tuple((
+                sp0,
+                alt((
+                    value(ArithmeticOperator::Mult, c('*')),
+                    value(ArithmeticOperator::Div, c('/')),
+                    value(ArithmeticOperator::Mod, c('%')),
+                )),
+                sp0,
+                power_expr,
+            ))),
+        )),
+        |v| {
+            v.1.into_iter()
+                .fold(v.0, |lhs, e| arithmetic(e.1, lhs, e.3))
+        },
+    )(input)
+}
+
+fn power_expr(input: &str) -> IResult<&str, Expr> {
+    map(
+        tuple((
+            unary_add_sub_expr,
+            many0(preceded(tuple((sp0, c('^'), sp0)), unary_add_sub_expr)),
+        )),
+        |v| {
+            v.1.into_iter().fold(v.0, |lhs, rhs| {
+                Expr::PowerExpr(PowerExpr {
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                })
+            })
+        },
+    )(input)
+}
+
+fn unary_add_sub_expr(input: &str) -> IResult<&str, Expr> {
+    alt((
+        map(
+            tuple((one_of("+-"), sp0, unary_add_sub_expr)),
+            |v| match v.0 {
+                '-' => Expr::UnarySubExpr(UnarySubExpr {
+                    child: Box::new(v.2),
+                }),
+                // Unary plus doesn't change the value
+                _ => v.2,
+            },
+        ),
+        property_lookup_expr,
+    ))(input)
+}
+
+enum PostfixOperator {
+    PropertyLookup(String),
+    Subscript(Expr),
+    Slice(Option<Expr>, Option<Expr>),
+}
+
+fn property_lookup_expr(input: &str) -> IResult<&str, Expr> {
+    map(
+        tuple((atom_expr, many0(preceded(sp0, postfix_operator)))),
+        |v| {
+            let mut expr = v.0;
+            for op in v.1.into_iter() {
+                expr = match op {
+                    PostfixOperator::PropertyLookup(prop_name) => {
+                        Expr::PropertyLookup(PropertyLookup {
+                            child: Box::new(expr),
+                            prop_name: prop_name,
+                        })
+                    }
+                    PostfixOperator::Subscript(index) => Expr::Subscript(Subscript {
+                        child: Box::new(expr),
+                        index: Box::new(index),
+                    }),
+                    PostfixOperator::Slice(from, to) => Expr::Slice(Slice {
+                        child: Box::new(expr),
+                        from: from.map(|v| Box::new(v)),
+                        to: to.map(|v| Box::new(v)),
+                    }),
+                };
+            }
+            expr
+        },
+    )(input)
+}
+
+fn postfix_operator(input: &str) -> IResult<&str, PostfixOperator> {
+    alt((
+        map(tuple((tag("."), sp0, symbolic_name)), |v| {
+            PostfixOperator::PropertyLookup(v.2)
+        }),
+        map(
+            tuple((
+                c('['),
+                sp0,
+                opt(terminated(expr, sp0)),
+                tag(".."),
+                sp0,
+                opt(terminated(expr, sp0)),
+                c(']'),
+            )),
+            |v| PostfixOperator::Slice(v.2, v.5),
+        ),
+        map(tuple((c('['), sp0, expr, sp0, c(']'))), |v| {
+            PostfixOperator::Subscript(v.2)
+        }),
+    ))(input)
+}
+
+fn atom_expr(input: &str) -> IResult<&str, Expr> {
+    alt((
+        literal,
+        parameter,
+        case_expr,
+        count_star,
+        function_call,
+        list_literal,
+        map(properties, |v| Expr::MapLit(v)),
+        map(tuple((c('('), sp0, expr, sp0, c(')'))), |v| v.2),
+        map_projection,
+        map(symbolic_name, |v| Expr::Variable(v)),
+    ))(input)
+}
+
+fn map_projection(input: &str) -> IResult<&str, Expr> {
+    map(
+        tuple((
+            symbolic_name,
+            sp0,
+            c('{'),
+            sp0,
+            opt(tuple((
+                map_projection_item,
+                many0(preceded(tuple((sp0, c(','), sp0)), map_projection_item)),
+            ))),
+            sp0,
+            c('}'),
+        )),
+        |v| {
+            let mut items = Vec::new();
+            if let Some((first, rest)) = v.4 {
+                items.push(first);
+                items.extend(rest);
+            }
+            Expr::MapProjection(MapProjection {
+                variable: v.0,
+                items: items,
+            })
+        },
+    )(input)
+}
+
+fn map_projection_item(input: &str) -> IResult<&str, MapProjectionItem> {
+    alt((
+        map(tuple((c('.'), sp0, c('*'))), |_| {
+            MapProjectionItem::AllProperties
+        }),
+        map(preceded(pair(c('.'), sp0), symbolic_name), |v| {
+            MapProjectionItem::Property(v)
+        }),
+        map(property_key_value, |v| MapProjectionItem::Literal(v.0, v.1)),
+        map(symbolic_name, |v| MapProjectionItem::Variable(v)),
+    ))(input)
+}
+
+fn count_star(input: &str) -> IResult<&str, Expr> {
+    map(
+        tuple((tag_no_case("COUNT"), sp0, c('('), sp0, c('*'), sp0, c(')'))),
+        |_| Expr::CountStar,
+    )(input)
+}
+
+fn function_call(input: &str) -> IResult<&str, Expr> {
+    map(
+        tuple((
+            symbolic_name,
+            many0(preceded(c('.'), symbolic_name)),
+            sp0,
+            c('('),
+            sp0,
+            opt(terminated(tag_no_case("DISTINCT"), sp1)),
+            opt(tuple((expr, many0(tuple((sp0, c(','), sp0, expr)))))),
+            sp0,
+            c(')'),
+        )),
+        |v| {
+            let mut name = v.0;
+            for part in v.1.into_iter() {
+                name.push('.');
+                name.push_str(&part);
+            }
+            let mut args = Vec::new();
+            if let Some((first, rest)) = v.6 {
+                args.push(first);
+                rest.into_iter().for_each(|v| args.push(v.3));
+            }
+            Expr::FunctionCall(FunctionCall {
+                name: name,
+                distinct: v.5.is_some(),
+                args: args,
+            })
+        },
+    )(input)
+}
+
+fn case_expr(input: &str) -> IResult<&str, Expr> {
+    map(
+        tuple((
+            tag_no_case("CASE"),
+            sp1,
+            // Generic CASE is tried first, otherwise WHEN is parsed as operand
+            alt((
+                map(case_alternatives, |v| (None, v)),
+                map(tuple((expr, sp1, case_alternatives)), |v| (Some(v.0), v.2)),
+            )),
+            opt(tuple((tag_no_case("ELSE"), sp1, expr, sp1))),
+            tag_no_case("END"),
+        )),
+        |v| {
+            let (operand, alternatives) = v.2;
+            Expr::CaseExpr(CaseExpr {
+                operand: operand.map(|v| Box::new(v)),
+                alternatives: alternatives,
+                default: v.3.map(|v| Box::new(v.2)),
+            })
+        },
+    )(input)
+}
+
+fn case_alternatives(input: &str) -> IResult<&str, Vec<(Expr, Expr)>> {
+    many1(map(
+        tuple((
+            tag_no_case("WHEN"),
+            sp1,
+            expr,
+            sp1,
+            tag_no_case("THEN"),
+            sp1,
+            expr,
+            sp1,
+        )),
+        |v| (v.2, v.6),
+    ))(input)
+}
+
+fn list_literal(input: &str) -> IResult<&str, Expr> {
+    map(
+        tuple((
+            c('['),
+            sp0,
+            opt(tuple((expr, many0(tuple((sp0, c(','), sp0, expr)))))),
+            sp0,
+            c(']'),
+        )),
+        |v| {
+            let mut elements = Vec::new();
+            if let Some((first, rest)) = v.2 {
+                elements.push(first);
+                rest.into_iter().for_each(|v| elements.push(v.3));
+            }
+            Expr::ListLit(elements)
+        },
+    )(input)
+}
+
+fn parameter(input: &str) -> IResult<&str, Expr> {
+    map(preceded(tag("$"), symbolic_name), |v| Expr::Parameter(v))(input)
+}
+
+fn literal(input: &str) -> IResult<&str, Expr> {
+    alt((
+        number_literal,
+        string_literal,
+        boolean_literal,
+        null_literal,
+    ))(input)
+}
+
+// Numbers with a fraction or an exponent are floats, and other numbers are
+// integers
+fn number_literal(input: &str) -> IResult<&str, Expr> {
+    alt((
+        // `1..2` in slice is not a float
+        map_res(
+            verify(terminated(recognize(double), not(tag("."))), |v: &str| {
+                v.contains(|c| c == '.' || c == 'e' || c == 'E')
+            }),
+            |v: &str| v.parse().map(Expr::NumberLit),
+        ),
+        integer_number_literal,
+    ))(input)
+}
+
+// Integer out of range is an error rather than another expression
+fn integer_number_literal(input: &str) -> IResult<&str, Expr> {
+    let (rest, (digits, radix)) = alt((
+        map(preceded(tag_no_case("0X"), hex_digit1), |v| (v, 16)),
+        map(digit1, |v| (v, 10)),
+    ))(input)?;
+    match i64::from_str_radix(digits, radix) {
+        Ok(v) => Ok((rest, Expr::IntegerLit(v))),
+        Err(_) => Err(nom::Err::Failure(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::TooLarge,
+        ))),
+    }
+}
 
 fn string_literal(input: &str) -> IResult<&str, Expr> {
     alt((
@@ -457,10 +1325,23 @@ fn string_literal(input: &str) -> IResult<&str, Expr> {
 }
 
 fn boolean_literal(input: &str) -> IResult<&str, Expr> {
-    alt((
-        map(tag_no_case("TRUE"), |_| Expr::BooleanLit(true)),
-        map(tag_no_case("FALSE"), |_| Expr::BooleanLit(false)),
-    ))(input)
+    terminated(
+        alt((
+            map(tag_no_case("TRUE"), |_| Expr::BooleanLit(true)),
+            map(tag_no_case("FALSE"), |_| Expr::BooleanLit(false)),
+        )),
+        not(take_while1(|v: char| v.is_alphanumeric() || v == '_')),
+    )(input)
+}
+
+fn null_literal(input: &str) -> IResult<&str, Expr> {
+    map(
+        terminated(
+            tag_no_case("NULL"),
+            not(take_while1(|v: char| v.is_alphanumeric() || v == '_')),
+        ),
+        |_| Expr::NullLit,
+    )(input)
 }
 
 fn sp0(input: &str) -> IResult<&str, ()> {
@@ -509,3 +1390,655 @@ fn test_parse_multiple_pattern() {
     let parser = Parser::new();
     // println!("{:#?}", parser.parse(query));
 }
+
+#[test]
+fn test_parse_create() {
+    let query = r#"
+    CREATE (n:Person {name: 'A'})-[:knows {since: 2020}]->(m:Person)
+    RETURN n, m;"#;
+    let parser = Parser::new();
+    let ast = parser.parse(query).unwrap();
+    assert_eq!(ast.unions[0].updating_clauses.len(), 1);
+}
+
+#[test]
+fn test_parse_merge() {
+    let query = r#"
+    MATCH (a:Person)
+    MERGE (a)-[r:knows]->(b:Person {name: 'B'})
+    ON CREATE SET r.since = 2020, b.age = 18
+    ON MATCH SET r.visits = 1
+    RETURN a, b;"#;
+    let parser = Parser::new();
+    let ast = parser.parse(query).unwrap();
+    match ast.unions[0].updating_clauses.get(0) {
+        Some(UpdatingClause::Merge(merge)) => {
+            assert_eq!(merge.on_create.len(), 2);
+            assert_eq!(merge.on_match.len(), 1);
+        }
+        clause @ _ => panic!("unexpected clause {:#?}", clause),
+    }
+    assert!(ast.unions[0].return_clause.is_some());
+}
+
+#[test]
+fn test_parse_set_remove() {
+    let query = r#"
+    MATCH (n:Person)
+    SET n.age = 18, n += {name: 'A'}, n:Student
+    REMOVE n.email, n:Teacher
+    RETURN n;"#;
+    let parser = Parser::new();
+    let ast = parser.parse(query).unwrap();
+    let union = ast.unions.get(0).unwrap();
+    match union.updating_clauses.as_slice() {
+        [UpdatingClause::Set(set), UpdatingClause::Remove(remove)] => {
+            assert_eq!(set.items.len(), 3);
+            assert_eq!(remove.items.len(), 2);
+        }
+        clauses @ _ => panic!("unexpected clauses {:#?}", clauses),
+    }
+}
+
+#[test]
+fn test_parse_delete() {
+    let query = r#"
+    MATCH (a:Person)-[r:knows]->(b:Person)
+    DELETE r
+    DETACH DELETE a, b"#;
+    let parser = Parser::new();
+    let ast = parser.parse(query).unwrap();
+    let union = ast.unions.get(0).unwrap();
+    match union.updating_clauses.as_slice() {
+        [UpdatingClause::Delete(delete), UpdatingClause::Delete(detach_delete)] => {
+            assert!(!delete.detach);
+            assert!(detach_delete.detach);
+            assert_eq!(detach_delete.exprs.len(), 2);
+        }
+        clauses @ _ => panic!("unexpected clauses {:#?}", clauses),
+    }
+}
+
+#[test]
+fn test_parse_with() {
+    let query = r#"
+    MATCH (a:Person)
+    WITH a AS b, a.age AS age WHERE age > 3
+    MATCH (b)-[r:knows]->(c:Person)
+    RETURN c"#;
+    let parser = Parser::new();
+    let ast = parser.parse(query).unwrap();
+    let union = ast.unions.get(0).unwrap();
+    assert_eq!(union.parts.len(), 1);
+    let with_clause = &union.parts.get(0).unwrap().with_clause;
+    assert_eq!(with_clause.projections.get(0).unwrap().1, "b");
+    assert_eq!(with_clause.filter.len(), 1);
+    assert_eq!(union.reading_clauses.len(), 1);
+
+    // Clauses can't follow RETURN, and nothing can follow `;`
+    for query in [
+        "MATCH (a) RETURN a WITH a",
+        "MATCH (a) RETURN a MATCH (b)",
+        "MATCH (a) RETURN a; RETURN a",
+        "MATCH (a) WITH a",
+    ]
+    .iter()
+    {
+        assert!(parser.parse(query).is_err(), "{}", query);
+    }
+    assert!(parser.parse("MATCH (a) RETURN a ; ").is_ok());
+}
+
+#[test]
+fn test_parse_order_by() {
+    let query = r#"
+    MATCH (a:Person)
+    WITH a ORDER BY a.age DESC LIMIT 10
+    RETURN a.name AS name ORDER BY a.age, name ASC SKIP 1 LIMIT 3"#;
+    let parser = Parser::new();
+    let ast = parser.parse(query).unwrap();
+    let union = ast.unions.get(0).unwrap();
+    let with_clause = &union.parts.get(0).unwrap().with_clause;
+    assert_eq!(with_clause.sort_items.len(), 1);
+    assert!(with_clause.skip.is_none());
+    assert!(with_clause.limit.is_some());
+    let return_clause = union.return_clause.as_ref().unwrap();
+    assert_eq!(return_clause.sort_items.len(), 2);
+    assert!(return_clause.skip.is_some());
+    assert!(return_clause.limit.is_some());
+}
+
+#[test]
+fn test_parse_union() {
+    let parser = Parser {};
+    let ast = parser
+        .parse(
+            r#"
+    MATCH (a:Person) RETURN a.name AS name
+    UNION ALL
+    MATCH (b:Company) RETURN b.name AS name
+    UNION
+    RETURN 'tigraph' AS name
+    "#,
+        )
+        .unwrap();
+    assert_eq!(ast.unions.len(), 3);
+    assert!(ast.unions[1].all);
+    assert!(!ast.unions[2].all);
+}
+
+#[test]
+fn test_parse_unwind() {
+    let query = r#"
+    MATCH (a:Person)
+    UNWIND a.friends AS name
+    RETURN a, name"#;
+    let parser = Parser::new();
+    let ast = parser.parse(query).unwrap();
+    let union = ast.unions.get(0).unwrap();
+    match union.reading_clauses.as_slice() {
+        [ReadingClause::Match(_), ReadingClause::Unwind(unwind)] => {
+            assert_eq!(unwind.name, "name");
+        }
+        _ => panic!("unexpected reading clauses"),
+    }
+}
+
+#[test]
+fn test_parse_optional_match() {
+    let query = r#"
+    MATCH (p:Person)
+    OPTIONAL MATCH (p)-[:owns]->(c:Car)
+    RETURN p, c"#;
+    let parser = Parser::new();
+    let ast = parser.parse(query).unwrap();
+    let union = ast.unions.get(0).unwrap();
+    match union.reading_clauses.as_slice() {
+        [ReadingClause::Match(m), ReadingClause::Match(optional)] => {
+            assert!(!m.optional);
+            assert!(optional.optional);
+        }
+        _ => panic!("unexpected reading clauses"),
+    }
+}
+
+#[test]
+fn test_parse_multiple_match() {
+    let query = r#"
+    MATCH (a:Person) WHERE a.age = 3
+    MATCH (a)-[:knows]->(b)
+    RETURN b"#;
+    let parser = Parser::new();
+    let ast = parser.parse(query).unwrap();
+    let union = ast.unions.get(0).unwrap();
+    match union.reading_clauses.as_slice() {
+        [ReadingClause::Match(first), ReadingClause::Match(second)] => {
+            assert_eq!(first.filter.len(), 1);
+            assert!(second.filter.is_empty());
+        }
+        _ => panic!("unexpected reading clauses"),
+    }
+}
+
+#[test]
+fn test_parse_parameter() {
+    let query = r#"
+    UNWIND $names AS name
+    MATCH (a:Person {name: name}) WHERE a.age = $age
+    RETURN a SKIP $skip LIMIT $limit"#;
+    let parser = Parser::new();
+    let ast = parser.parse(query).unwrap();
+    let union = ast.unions.get(0).unwrap();
+    match union.reading_clauses.get(0) {
+        Some(ReadingClause::Unwind(unwind)) => {
+            assert!(matches!(&unwind.expr, Expr::Parameter(name) if name == "names"));
+        }
+        _ => panic!("unexpected reading clause"),
+    }
+    let return_clause = union.return_clause.as_ref().unwrap();
+    assert!(matches!(&return_clause.limit, Some(Expr::Parameter(name)) if name == "limit"));
+}
+
+#[cfg(test)]
+fn parse_expr(expr: &str) -> Option<String> {
+    let query = format!("RETURN {} AS v", expr);
+    let ast = Parser::new().parse(&query).ok()?;
+    let union = ast.unions.get(0).unwrap();
+    Some(
+        union.return_clause.as_ref().unwrap().projections[0]
+            .0
+            .to_string(),
+    )
+}
+
+#[test]
+fn test_parse_list_map() {
+    let cases = vec![
+        ("[1, 2, [3, 4]]", Some("[1, 2, [3, 4]]")),
+        ("[]", Some("[]")),
+        ("{k: x, v: 'v'}", Some("{k: x, v: v}")),
+        ("x[0]", Some("x[0]")),
+        ("x[0][1]", Some("x[0][1]")),
+        ("x[1..2]", Some("x[1..2]")),
+        ("x[..1]", Some("x[..1]")),
+        ("x[1..]", Some("x[1..]")),
+        ("x[..]", Some("x[..]")),
+        ("{k: x}['k']", Some("{k: x}[k]")),
+        ("[1, 2", None),
+        ("{k}", None),
+        ("x[1..2..3]", None),
+    ];
+    for (expr, expected) in cases {
+        assert_eq!(parse_expr(expr).as_deref(), expected, "{}", expr);
+    }
+}
+
+#[test]
+fn test_parse_null() {
+    let query = r#"
+    MATCH (a:Person) WHERE a.age IS NULL OR a.name IS NOT NULL
+    RETURN a, null AS n, nullable"#;
+    let parser = Parser::new();
+    let ast = parser.parse(query).unwrap();
+    let union = ast.unions.get(0).unwrap();
+    match union.reading_clauses.get(0) {
+        Some(ReadingClause::Match(m)) => match m.filter.get(0) {
+            Some(Expr::OrExpr(or)) => {
+                assert!(matches!(*or.lhs, Expr::IsNullExpr(_)));
+                assert!(matches!(*or.rhs, Expr::IsNotNullExpr(_)));
+            }
+            _ => panic!("unexpected filter"),
+        },
+        _ => panic!("unexpected reading clause"),
+    }
+    let projections = &union.return_clause.as_ref().unwrap().projections;
+    assert!(matches!(projections[1].0, Expr::NullLit));
+    assert!(matches!(&projections[2].0, Expr::Variable(name) if name == "nullable"));
+
+    // Keywords of literals are not prefixes of variables
+    let query = "RETURN true AS a, FALSE AS b, trueish AS c, false_ AS d";
+    let ast = parser.parse(query).unwrap();
+    let union = ast.unions.get(0).unwrap();
+    let projections = &union.return_clause.as_ref().unwrap().projections;
+    assert!(matches!(projections[0].0, Expr::BooleanLit(true)));
+    assert!(matches!(projections[1].0, Expr::BooleanLit(false)));
+    assert!(matches!(&projections[2].0, Expr::Variable(name) if name == "trueish"));
+    assert!(matches!(&projections[3].0, Expr::Variable(name) if name == "false_"));
+}
+
+#[test]
+fn test_parse_string_predicate() {
+    let query = r#"
+    MATCH (a:Person)
+    WHERE a.name STARTS WITH 'A' AND a.email ENDS WITH '.com'
+    RETURN a.name CONTAINS 'b' AS b, a.name =~ 'A.*' AS c"#;
+    let parser = Parser::new();
+    let ast = parser.parse(query).unwrap();
+    let union = ast.unions.get(0).unwrap();
+    match union.reading_clauses.get(0) {
+        Some(ReadingClause::Match(m)) => match m.filter.get(0) {
+            Some(Expr::AndExpr(and)) => {
+                assert!(matches!(*and.lhs, Expr::StartsWithExpr(_)));
+                assert!(matches!(*and.rhs, Expr::EndsWithExpr(_)));
+            }
+            _ => panic!("unexpected filter"),
+        },
+        _ => panic!("unexpected reading clause"),
+    }
+    let projections = &union.return_clause.as_ref().unwrap().projections;
+    assert!(matches!(projections[0].0, Expr::ContainsExpr(_)));
+    assert!(matches!(projections[1].0, Expr::RegexMatchExpr(_)));
+}
+
+#[test]
+fn test_parse_in() {
+    let cases = vec![
+        ("a.id IN [1, 2, 3]", Some("a.id IN [1, 2, 3]")),
+        ("a.name IN $names", Some("a.name IN $names")),
+        ("x IN []", Some("x IN []")),
+        ("null IN [null]", Some("NULL IN [NULL]")),
+        ("NOT x IN y", Some("NOT x IN y")),
+        ("x IN y AND z", Some("x IN y AND z")),
+        ("index", Some("index")),
+        ("x IN", None),
+    ];
+    for (expr, expected) in cases {
+        assert_eq!(parse_expr(expr).as_deref(), expected, "{}", expr);
+    }
+}
+
+#[test]
+fn test_parse_case() {
+    let cases = vec![
+        (
+            "CASE a.age WHEN 1 THEN 'baby' WHEN 2 THEN 'toddler' END",
+            Some("CASE a.age WHEN 1 THEN baby WHEN 2 THEN toddler END"),
+        ),
+        (
+            "CASE WHEN a.age > 18 THEN 'adult' ELSE 'minor' END",
+            Some("CASE WHEN a.age>18 THEN adult ELSE minor END"),
+        ),
+        (
+            "case x when null then 1 else 2 end",
+            Some("CASE x WHEN NULL THEN 1 ELSE 2 END"),
+        ),
+        (
+            "CASE WHEN x THEN CASE y WHEN 1 THEN 2 END END",
+            Some("CASE WHEN x THEN CASE y WHEN 1 THEN 2 END END"),
+        ),
+        ("casex", Some("casex")),
+        ("CASE x END", None),
+        ("CASE WHEN x THEN 1", None),
+        ("CASE ELSE 1 END", None),
+    ];
+    for (expr, expected) in cases {
+        assert_eq!(parse_expr(expr).as_deref(), expected, "{}", expr);
+    }
+}
+
+#[test]
+fn test_parse_function_call() {
+    let query = r#"
+    MATCH (a:Person)
+    RETURN toUpper(a.name) AS name, coalesce(a.nickname, a.name, 'unknown') AS nickname,
+        a.b.c() AS c, size([]) AS s"#;
+    let parser = Parser::new();
+    let ast = parser.parse(query).unwrap();
+    let union = ast.unions.get(0).unwrap();
+    let projections = &union.return_clause.as_ref().unwrap().projections;
+    match &projections[0].0 {
+        Expr::FunctionCall(call) => {
+            assert_eq!(call.name, "toUpper");
+            assert_eq!(call.args.len(), 1);
+        }
+        _ => panic!("unexpected expression"),
+    }
+    assert!(matches!(&projections[1].0, Expr::FunctionCall(call) if call.args.len() == 3));
+    assert!(
+        matches!(&projections[2].0, Expr::FunctionCall(call) if call.name == "a.b.c" && call.args.is_empty())
+    );
+}
+
+#[test]
+fn test_parse_aggregate() {
+    let query = r#"
+    MATCH (a:Person)
+    RETURN a.city, count(*) AS total, COUNT( * ), count(DISTINCT a.name) AS names"#;
+    let parser = Parser::new();
+    let ast = parser.parse(query).unwrap();
+    let union = ast.unions.get(0).unwrap();
+    let projections = &union.return_clause.as_ref().unwrap().projections;
+    assert!(matches!(&projections[1].0, Expr::CountStar));
+    assert!(matches!(&projections[2].0, Expr::CountStar));
+    match &projections[3].0 {
+        Expr::FunctionCall(call) => {
+            assert_eq!(call.name, "count");
+            assert!(call.distinct);
+            assert_eq!(call.args.len(), 1);
+        }
+        _ => panic!("unexpected expression"),
+    }
+}
+
+#[cfg(test)]
+fn parse_pattern(pattern: &str) -> Option<Pattern> {
+    let query = format!("MATCH {} RETURN *", pattern);
+    let mut ast = Parser::new().parse(&query).ok()?;
+    match ast.unions.remove(0).reading_clauses.remove(0) {
+        ReadingClause::Match(mut m) => Some(m.pattern.remove(0)),
+        _ => panic!("unexpected clause"),
+    }
+}
+
+#[test]
+fn test_parse_var_length_relationship() {
+    let cases = vec![
+        ("(a)-[:knows*]->(b)", Some(Some((None, None)))),
+        ("(a)-[r:knows*2]->(b)", Some(Some((Some(2), Some(2))))),
+        ("(a)<-[:knows*1..3]-(b)", Some(Some((Some(1), Some(3))))),
+        (
+            "(a)-[:knows*..3 {since: 2020}]->(b)",
+            Some(Some((None, Some(3)))),
+        ),
+        ("(a)-[:knows* 2 ..]->(b)", Some(Some((Some(2), None)))),
+        ("(a)-[*0..1]->(b)", Some(Some((Some(0), Some(1))))),
+        ("(a)-[:knows]->(b)", Some(None)),
+        ("(a)-[:knows*1..2..3]->(b)", None),
+        ("(a)-[:knows*-1]->(b)", None),
+    ];
+    for (pattern, expected) in cases {
+        let length =
+            parse_pattern(pattern).map(|v| v.rels[0].length.as_ref().map(|v| (v.min, v.max)));
+        assert_eq!(length, expected, "{}", pattern);
+    }
+}
+
+#[test]
+fn test_parse_undirected_relationship() {
+    let cases = vec![
+        ("(a)-[r:coauthor]-(b)", Some(vec!["Both"])),
+        ("(a)-[r:coauthor]->(b)", Some(vec!["Right"])),
+        ("(a)<-[r:coauthor]-(b)", Some(vec!["Left"])),
+        ("(a) - [r] - (b)", Some(vec!["Both"])),
+        ("(a)-[:knows*1..2]-(b)", Some(vec!["Both"])),
+        (
+            "(a)-[r:coauthor]-(b)<-[:knows]-(c)-[:knows]->(d)",
+            Some(vec!["Both", "Left", "Right"]),
+        ),
+        ("(a)-[r:coauthor](b)", None),
+        ("(a)[r:coauthor]-(b)", None),
+    ];
+    for (pattern, expected) in cases {
+        let directions = parse_pattern(pattern).map(|v| {
+            v.rels
+                .iter()
+                .map(|v| format!("{:?}", v.direction))
+                .collect::<Vec<String>>()
+        });
+        assert_eq!(
+            directions,
+            expected.map(|v| v.iter().map(|v| v.to_string()).collect()),
+            "{}",
+            pattern
+        );
+    }
+}
+
+// Pattern written back with names, labels, types and directions
+#[cfg(test)]
+fn pattern_text(pattern: &Pattern) -> String {
+    let node = |node: &NodePattern| {
+        let labels: Vec<String> = node.labels.iter().map(|v| format!(":{}", v)).collect();
+        format!(
+            "({}{})",
+            node.name.as_deref().unwrap_or(""),
+            labels.concat()
+        )
+    };
+    let mut text = node(&pattern.nodes[0]);
+    for (rel, end) in pattern.rels.iter().zip(pattern.nodes[1..].iter()) {
+        let types = if rel.types.is_empty() {
+            String::new()
+        } else {
+            format!(":{}", rel.types.join("|"))
+        };
+        let detail = format!("[{}{}]", rel.name.as_deref().unwrap_or(""), types);
+        text += &match rel.direction {
+            Direction::Left => format!("<-{}-", detail),
+            Direction::Right => format!("-{}->", detail),
+            Direction::Both => format!("-{}-", detail),
+        };
+        text += &node(end);
+    }
+    text
+}
+
+#[test]
+fn test_parse_anonymous_pattern() {
+    let cases = vec![
+        ("()", Some("()")),
+        ("( )", Some("()")),
+        ("(:Person)", Some("(:Person)")),
+        (
+            "(a:Person)-->(:Person)<--()",
+            Some("(a:Person)-[]->(:Person)<-[]-()"),
+        ),
+        ("(a)-[:knows]->()", Some("(a)-[:knows]->()")),
+        ("(a)<-[]-(b)", Some("(a)<-[]-(b)")),
+        ("(a)--(b)", Some("(a)-[]-(b)")),
+        ("()-[r]->()", Some("()-[r]->()")),
+        ("(a)->(b)", None),
+        ("(a)-[:]->(b)", None),
+        ("(:)", None),
+    ];
+    for (pattern, expected) in cases {
+        let text = parse_pattern(pattern).map(|v| pattern_text(&v));
+        assert_eq!(text.as_deref(), expected, "{}", pattern);
+    }
+}
+
+#[test]
+fn test_parse_multiple_labels_and_types() {
+    let query = r#"
+    MATCH (n)-[r:knows|likes]->(m:Person:Author)-[:knows | :likes *2]-()
+    RETURN n"#;
+    let parser = Parser::new();
+    let ast = parser.parse(query).unwrap();
+    let union = ast.unions.get(0).unwrap();
+    let pattern = match &union.reading_clauses[0] {
+        ReadingClause::Match(m) => &m.pattern[0],
+        _ => panic!("unexpected clause"),
+    };
+    assert!(pattern.nodes[0].labels.is_empty());
+    assert_eq!(
+        pattern.nodes[1].labels,
+        vec!["Person".to_owned(), "Author".to_owned()]
+    );
+    assert_eq!(
+        pattern.rels[0].types,
+        vec!["knows".to_owned(), "likes".to_owned()]
+    );
+    assert_eq!(
+        pattern.rels[1].types,
+        vec!["knows".to_owned(), "likes".to_owned()]
+    );
+    assert!(pattern.rels[1].length.is_some());
+}
+
+#[test]
+fn test_parse_expression_precedence() {
+    let query = r#"
+    MATCH (a:Person)
+    RETURN 1 + 2 * 3 - 4 AS a, a AND b AND c AS b, 1 < x <= 3 AS c, x <> 1 AS d,
+        x<=1 AS e, NOT NOT x AS f, (1 + 2)*3 AS g, +1 AS h, 2 ^ 3 ^ 2 AS i,
+        a.name=~'A.*' AS j, note AS k, 7 % 2 AS l"#;
+    let parser = Parser::new();
+    let ast = parser.parse(query).unwrap();
+    let union = ast.unions.get(0).unwrap();
+    let projections = &union.return_clause.as_ref().unwrap().projections;
+    assert_eq!(projections.len(), 12);
+    match &projections[0].0 {
+        Expr::SubExpr(sub) => match sub.lhs.as_ref() {
+            Expr::AddExpr(add) => assert!(matches!(add.rhs.as_ref(), Expr::MultExpr(_))),
+            _ => panic!("unexpected expression"),
+        },
+        _ => panic!("unexpected expression"),
+    }
+    assert!(
+        matches!(&projections[1].0, Expr::AndExpr(and) if matches!(and.lhs.as_ref(), Expr::AndExpr(_)))
+    );
+    match &projections[2].0 {
+        Expr::AndExpr(and) => {
+            assert!(matches!(and.lhs.as_ref(), Expr::LessExpr(_)));
+            assert!(
+                matches!(and.rhs.as_ref(), Expr::LessEqualExpr(e) if matches!(e.lhs.as_ref(), Expr::Variable(_)))
+            );
+        }
+        _ => panic!("unexpected expression"),
+    }
+    assert!(matches!(&projections[3].0, Expr::NotEqualExpr(_)));
+    assert!(matches!(&projections[4].0, Expr::LessEqualExpr(_)));
+    assert!(
+        matches!(&projections[5].0, Expr::NotExpr(not) if matches!(not.child.as_ref(), Expr::NotExpr(_)))
+    );
+    assert!(
+        matches!(&projections[6].0, Expr::MultExpr(mult) if matches!(mult.lhs.as_ref(), Expr::AddExpr(_)))
+    );
+    assert!(matches!(&projections[7].0, Expr::IntegerLit(1)));
+    assert!(
+        matches!(&projections[8].0, Expr::PowerExpr(power) if matches!(power.lhs.as_ref(), Expr::PowerExpr(_)))
+    );
+    assert!(matches!(&projections[9].0, Expr::RegexMatchExpr(_)));
+    assert!(matches!(&projections[10].0, Expr::Variable(name) if name == "note"));
+    assert!(matches!(&projections[11].0, Expr::ModExpr(_)));
+}
+
+#[test]
+fn test_parse_map_projection() {
+    let query = r#"
+    MATCH (a:Person)
+    RETURN a.address.city AS city, (a).name AS name,
+        a {.name, .*, friends: size(fs), age} AS person, a {} AS empty"#;
+    let parser = Parser::new();
+    let ast = parser.parse(query).unwrap();
+    let union = ast.unions.get(0).unwrap();
+    let projections = &union.return_clause.as_ref().unwrap().projections;
+    match &projections[0].0 {
+        Expr::PropertyLookup(lookup) => {
+            assert_eq!(lookup.prop_name, "city");
+            assert!(matches!(lookup.child.as_ref(), Expr::PropertyLookup(_)));
+        }
+        _ => panic!("unexpected expression"),
+    }
+    assert!(
+        matches!(&projections[1].0, Expr::PropertyLookup(lookup) if matches!(lookup.child.as_ref(), Expr::Variable(_)))
+    );
+    match &projections[2].0 {
+        Expr::MapProjection(projection) => {
+            assert_eq!(projection.variable, "a");
+            assert_eq!(projection.items.len(), 4);
+            assert!(
+                matches!(&projection.items[0], MapProjectionItem::Property(key) if key == "name")
+            );
+            assert!(matches!(
+                &projection.items[1],
+                MapProjectionItem::AllProperties
+            ));
+            assert!(
+                matches!(&projection.items[2], MapProjectionItem::Literal(key, _) if key == "friends")
+            );
+            assert!(
+                matches!(&projection.items[3], MapProjectionItem::Variable(name) if name == "age")
+            );
+        }
+        _ => panic!("unexpected expression"),
+    }
+    assert!(
+        matches!(&projections[3].0, Expr::MapProjection(projection) if projection.items.is_empty())
+    );
+}
+
+#[test]
+fn test_parse_number_literal() {
+    let query = "RETURN 7 AS a, 0x1F AS b, 1.5 AS c, 1e3 AS d, .5 AS e, x[1..2] AS f";
+    let ast = Parser::new().parse(query).unwrap();
+    let union = ast.unions.get(0).unwrap();
+    let projections = &union.return_clause.as_ref().unwrap().projections;
+    assert!(matches!(&projections[0].0, Expr::IntegerLit(7)));
+    assert!(matches!(&projections[1].0, Expr::IntegerLit(31)));
+    assert!(matches!(&projections[2].0, Expr::NumberLit(v) if *v == 1.5));
+    assert!(matches!(&projections[3].0, Expr::NumberLit(v) if *v == 1000.0));
+    assert!(matches!(&projections[4].0, Expr::NumberLit(v) if *v == 0.5));
+    match &projections[5].0 {
+        Expr::Slice(slice) => {
+            assert!(matches!(slice.from.as_deref(), Some(Expr::IntegerLit(1))));
+            assert!(matches!(slice.to.as_deref(), Some(Expr::IntegerLit(2))));
+        }
+        _ => panic!("unexpected expression"),
+    }
+
+    // Integer out of range
+    assert!(Parser::new()
+        .parse("RETURN 9223372036854775808 AS a")
+        .is_err());
+}
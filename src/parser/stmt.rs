@@ -14,48 +14,83 @@ pub enum SortOrdering {
 #[derive(Debug)]
 pub struct Query {
     pub unions: Vec<Union>,
-    pub sort_items: Vec<Expr>,
-    pub ordering: Option<SortOrdering>,
-    pub skip: Option<Expr>,
-    pub limit: Option<Expr>,
+}
+
+#[derive(Debug)]
+pub struct SortItem {
+    pub expr: Expr,
+    pub ordering: SortOrdering,
 }
 
 #[derive(Debug)]
 pub struct Union {
-    pub reading_clause: Option<ReadingClause>,
+    // Combined with the previous query by UNION ALL instead of UNION
+    pub all: bool,
+    // Leading parts of multi-part query
+    pub parts: Vec<QueryPart>,
+    pub reading_clauses: Vec<ReadingClause>,
     pub updating_clauses: Vec<UpdatingClause>,
-    pub return_clause: ReturnClause,
+    pub return_clause: Option<ReturnClause>,
+}
+
+// Part of multi-part query, which is ended with a WITH clause
+#[derive(Debug)]
+pub struct QueryPart {
+    pub reading_clauses: Vec<ReadingClause>,
+    pub updating_clauses: Vec<UpdatingClause>,
+    pub with_clause: WithClause,
 }
 
 #[derive(Debug)]
 pub enum ReadingClause {
     Match(MatchClause),
-    Unwind,
+    Unwind(UnwindClause),
+}
+
+#[derive(Debug)]
+pub struct UnwindClause {
+    pub expr: Expr,
+    pub name: String,
 }
 
 #[derive(Debug)]
 pub struct MatchClause {
+    pub optional: bool,
     pub pattern: Vec<Pattern>,
     pub filter: Vec<Expr>,
 }
 
 #[derive(Debug)]
 pub struct NodePattern {
-    pub name: String,
+    // Anonymous node if not given
+    pub name: Option<String>,
     pub labels: Vec<String>,
+    pub properties: Vec<(String, Expr)>,
 }
 
 #[derive(Debug)]
 pub enum Direction {
     Left,
     Right,
+    // Undirected, e.g. `(a)-[r]-(b)`
+    Both,
+}
+
+// Bounds of hops of variable length relationship, e.g. `*1..3`
+#[derive(Debug)]
+pub struct PathLength {
+    pub min: Option<u64>,
+    pub max: Option<u64>,
 }
 
 #[derive(Debug)]
 pub struct RelationshipPattern {
-    pub name: String,
+    pub name: Option<String>,
     pub direction: Direction,
     pub types: Vec<String>,
+    // Variable length relationship if given
+    pub length: Option<PathLength>,
+    pub properties: Vec<(String, Expr)>,
 }
 
 #[derive(Debug)]
@@ -66,14 +101,95 @@ pub struct Pattern {
 
 #[derive(Debug)]
 pub enum UpdatingClause {
-    Create,
-    Merge,
-    Remove,
-    Set,
+    Create(CreateClause),
+    Delete(DeleteClause),
+    Merge(MergeClause),
+    Remove(RemoveClause),
+    Set(SetClause),
+}
+
+#[derive(Debug)]
+pub struct CreateClause {
+    pub pattern: Vec<Pattern>,
+}
+
+#[derive(Debug)]
+pub struct DeleteClause {
+    pub detach: bool,
+    pub exprs: Vec<Expr>,
+}
+
+#[derive(Debug)]
+pub struct MergeClause {
+    pub pattern: Pattern,
+    pub on_match: Vec<SetItem>,
+    pub on_create: Vec<SetItem>,
+}
+
+#[derive(Debug)]
+pub struct SetClause {
+    pub items: Vec<SetItem>,
+}
+
+#[derive(Debug)]
+pub enum SetItem {
+    // n.prop = expr
+    Property {
+        variable: String,
+        key: String,
+        value: Expr,
+    },
+    // n = {...}
+    Replace {
+        variable: String,
+        properties: Vec<(String, Expr)>,
+    },
+    // n += {...}
+    Append {
+        variable: String,
+        properties: Vec<(String, Expr)>,
+    },
+    // n:Label
+    Labels {
+        variable: String,
+        labels: Vec<String>,
+    },
+}
+
+#[derive(Debug)]
+pub struct RemoveClause {
+    pub items: Vec<RemoveItem>,
+}
+
+#[derive(Debug)]
+pub enum RemoveItem {
+    // n.prop
+    Property {
+        variable: String,
+        key: String,
+    },
+    // n:Label
+    Labels {
+        variable: String,
+        labels: Vec<String>,
+    },
+}
+
+#[derive(Debug)]
+pub struct WithClause {
+    pub star: bool,
+    pub projections: Vec<(Expr, String)>,
+    pub sort_items: Vec<SortItem>,
+    pub skip: Option<Expr>,
+    pub limit: Option<Expr>,
+    pub filter: Vec<Expr>,
 }
 
 #[derive(Debug)]
 pub struct ReturnClause {
     pub star: bool,
     pub projections: Vec<(Expr, String)>,
+    pub sort_items: Vec<SortItem>,
+    pub skip: Option<Expr>,
+    pub limit: Option<Expr>,
 }
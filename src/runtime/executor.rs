@@ -1,27 +1,51 @@
 use crate::{
     core::Context,
-    optimizer::{ExpandExpr, ProjectExpr, RelExpr, ScalarExpr, SelectExpr},
-    types::Object,
+    optimizer::{
+        AggregateCall, AggregateExpr, CreateExpr, CreateNode, CreateRel, DeleteExpr, DeleteItem,
+        ExpandExpr, JoinExpr, JoinType, LeftOuterApplyExpr, LimitExpr, MergeExpr, ProjectExpr,
+        RelExpr, ScalarExpr, SelectExpr, SortExpr, SortKey, TopNExpr, UnionExpr, UnwindExpr,
+        UpdateExpr, UpdateItem, VarLengthExpandExpr,
+    },
+    types::{Array, Object},
 };
 use crate::{core::TiDBService, types::Value};
-use crate::{optimizer::ScanExpr, Error};
+use crate::{optimizer::ScanExpr, util::ErrorKind, Error};
 use mysql::{
+    consts::ColumnType,
     prelude::{FromRow, Queryable},
-    Pool, PooledConn, Row,
+    Column, Pool, PooledConn, Row,
 };
-use std::collections::{HashMap, VecDeque};
+use std::{
+    cell::{RefCell, RefMut},
+    cmp::Ordering,
+    collections::{HashMap, HashSet, VecDeque},
+    ops::{Deref, DerefMut},
+    rc::Rc,
+};
+
+use super::expression::{eval, Accumulator};
+use super::sql::condition;
 
-use super::expression::eval;
+// Values of query parameters, which are shared by all rows of a query
+pub type Params = Rc<HashMap<String, Value>>;
 
 #[derive(Clone, Debug)]
 pub struct ExecutionContext {
     pub values: HashMap<String, Value>,
+    // Parameters are binded when executing plan, so a plan can be executed
+    // with different parameters
+    pub params: Params,
 }
 
 impl ExecutionContext {
     pub fn new() -> ExecutionContext {
+        Self::with_params(&Rc::new(HashMap::new()))
+    }
+
+    pub fn with_params(params: &Params) -> ExecutionContext {
         ExecutionContext {
             values: HashMap::new(),
+            params: params.clone(),
         }
     }
 
@@ -38,6 +62,10 @@ impl ExecutionContext {
     }
 }
 
+// Connection of a transaction, which is shared by executors running in it.
+// Executors without session use their own connections.
+pub type Session = Rc<RefCell<Option<PooledConn>>>;
+
 pub trait Executor {
     fn open(&mut self) -> Result<(), Error>;
     fn next(&mut self) -> Result<Option<ExecutionContext>, Error>;
@@ -46,18 +74,33 @@ pub trait Executor {
 
 pub struct TiDBScanExec {
     context: Context,
+    session: Option<Session>,
+    params: Params,
     result: VecDeque<ExecutionContext>,
 
     binded_name: String,
     all: bool,
-    label: String,
+    labels: Vec<String>,
+    filter: Vec<ScalarExpr>,
 }
 
 impl Executor for TiDBScanExec {
     fn open(&mut self) -> Result<(), Error> {
-        let mut conn = prepare_tidb_connection(&self.context)?;
-        let res = scan_table(&self.table_name(), &self.binded_name, &mut conn)?;
-        self.result = VecDeque::from(res);
+        let mut conn = prepare_connection(&self.context, &self.session)?;
+        let labels = if self.all {
+            Vec::new()
+        } else {
+            self.labels.to_owned()
+        };
+        let res = scan_nodes(
+            &self.context,
+            &labels,
+            &self.binded_name,
+            &self.filter,
+            &self.params,
+            &mut conn,
+        )?;
+        self.result = res.into_iter().collect();
 
         Ok(())
     }
@@ -72,23 +115,19 @@ impl Executor for TiDBScanExec {
 }
 
 impl TiDBScanExec {
-    pub fn new(expr: &ScanExpr) -> TiDBScanExec {
+    pub fn new(expr: &ScanExpr, session: Option<Session>, params: &Params) -> TiDBScanExec {
         TiDBScanExec {
             binded_name: expr.binded_name.to_owned(),
             all: expr.all,
-            label: expr.label.to_owned(),
+            labels: expr.labels.to_owned(),
+            filter: expr.filter.to_owned(),
             context: Context::new(),
+            session: session,
+            params: params.clone(),
             result: VecDeque::new(),
         }
     }
 
-    fn table_name(&self) -> String {
-        match self.context.tidb_service.label_table_map.get(&self.label) {
-            Some(name) => name.to_owned(),
-            None => self.label.to_owned(),
-        }
-    }
-
     fn scan_sql(&self, table_name: String) -> String {
         format!(r#"select * from {};"#, table_name)
     }
@@ -116,7 +155,7 @@ impl Executor for ProjectExec {
                     }
                     Ok(Some(ctx))
                 } else {
-                    let mut res = ExecutionContext::new();
+                    let mut res = ExecutionContext::with_params(&ctx.params);
                     for p in self.projects.iter() {
                         let v = eval(&p.0, &ctx)?;
                         res.set(&p.1, v);
@@ -145,12 +184,17 @@ impl ProjectExec {
 
 pub struct TiDBNestedLoopExpand {
     context: Context,
+    session: Option<Session>,
+    params: Params,
     result: VecDeque<ExecutionContext>,
     start_name: String,
     end_name: String,
     rel_name: String,
-    rel_type: String,
-    end_label: String,
+    rel_types: Vec<String>,
+    end_labels: Vec<String>,
+    undirected: bool,
+    rel_filter: Vec<ScalarExpr>,
+    end_filter: Vec<ScalarExpr>,
 
     child: Box<dyn Executor>,
 }
@@ -158,52 +202,108 @@ pub struct TiDBNestedLoopExpand {
 impl Executor for TiDBNestedLoopExpand {
     fn open(&mut self) -> Result<(), Error> {
         self.child.open()?;
-        let mut conn = prepare_tidb_connection(&self.context)?;
-        let rels = self.fetch_relationships(&mut conn)?;
-        let end_nodes = self.fetch_end_nodes(&mut conn)?;
+        // Connection borrows the session, so it's released before joining
+        let (rels, end_nodes) = {
+            let mut conn = prepare_connection(&self.context, &self.session)?;
+            let rels = self.fetch_relationships(&mut conn)?;
+            let end_nodes = self.fetch_end_nodes(&mut conn)?;
+            (rels, end_nodes)
+        };
+
+        self.result.clear();
+        while let Some(ctx) = self.child.next()? {
+            self.expand_row(&ctx, &rels, &end_nodes);
+        }
+
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), Error> {
+        self.child.close()
+    }
+
+    fn next(&mut self) -> Result<Option<ExecutionContext>, Error> {
+        Ok(self.result.pop_front())
+    }
+}
+
+impl TiDBNestedLoopExpand {
+    pub fn new(
+        child: Box<dyn Executor>,
+        expand: &ExpandExpr,
+        session: Option<Session>,
+        params: &Params,
+    ) -> TiDBNestedLoopExpand {
+        TiDBNestedLoopExpand {
+            context: Context::new(),
+            session: session,
+            params: params.clone(),
+            result: VecDeque::new(),
+            start_name: expand.start_name.to_owned(),
+            end_name: expand.end_name.to_owned(),
+            rel_name: expand.rel_name.to_owned(),
+            rel_types: expand.rel_types.to_owned(),
+            end_labels: expand.end_labels.to_owned(),
+            undirected: expand.undirected,
+            rel_filter: expand.rel_filter.to_owned(),
+            end_filter: expand.end_filter.to_owned(),
+
+            child: child,
+        }
+    }
+
+    // Join node of the input row with rels, then with end
+    fn expand_row(
+        &mut self,
+        ctx: &ExecutionContext,
+        rels: &Vec<ExecutionContext>,
+        end_nodes: &Vec<ExecutionContext>,
+    ) {
         let start_id = "id";
         let end_id = "id";
         let rel_id = ("start", "end");
 
+        // Id of node on the other side of relationship is kept for joining
+        // with end
         let mut result = Vec::new();
-
-        // println!("Relationships: {:#?}", rels);
-        // println!("End Nodes: {:#?}", end_nodes);
-
-        // Join with rels
-        while let Ok(Some(ref ctx)) = self.child.next() {
-            for rel in rels.iter() {
-                match (
-                    ctx.get(self.start_name.as_str()),
-                    rel.get(self.rel_name.as_str()),
-                ) {
-                    (Some(Value::Object(l)), Some(Value::Object(r))) => {
-                        if match (l.get(start_id), r.get(rel_id.0)) {
-                            (Some(l), Some(r)) => l == r,
-                            _ => false,
-                        } {
-                            let mut res = ctx.clone();
-                            res.set(&self.rel_name, Value::Object(r.to_owned()));
-                            result.push(res);
-                        }
+        for rel in rels.iter() {
+            match (
+                ctx.get(self.start_name.as_str()),
+                rel.get(self.rel_name.as_str()),
+            ) {
+                (Some(Value::Object(l)), Some(Value::Object(r))) => {
+                    let start = match l.get(start_id) {
+                        Some(start) => start,
+                        None => continue,
+                    };
+                    // Undirected relationship can be traversed from end to start
+                    let other = if r.get(rel_id.0) == Some(start) {
+                        r.get(rel_id.1)
+                    } else if self.undirected && r.get(rel_id.1) == Some(start) {
+                        r.get(rel_id.0)
+                    } else {
+                        None
+                    };
+                    if let Some(other) = other {
+                        let mut res = ctx.clone();
+                        res.set(&self.rel_name, Value::Object(r.to_owned()));
+                        result.push((res, other.to_owned()));
                     }
-                    _ => {}
                 }
+                _ => {}
             }
         }
 
-        // Join with end
-        for ctx in result.iter() {
+        for (ctx, other) in result.iter() {
             for end in end_nodes.iter() {
-                match (
-                    ctx.get(self.rel_name.as_str()),
-                    end.get(self.end_name.as_str()),
-                ) {
-                    (Some(Value::Object(l)), Some(Value::Object(r))) => {
-                        if match (l.get(rel_id.1), r.get(end_id)) {
-                            (Some(l), Some(r)) => l == r,
-                            _ => false,
-                        } {
+                match end.get(self.end_name.as_str()) {
+                    Some(Value::Object(r)) => {
+                        // End node may have been binded by previous clauses
+                        let bound = match ctx.get(self.end_name.as_str()) {
+                            Some(Value::Object(bound)) => bound.get(end_id) == r.get(end_id),
+                            _ => true,
+                        };
+                        if bound && r.get(end_id) == Some(other) {
                             let mut res = ctx.clone();
                             res.set(&self.end_name, Value::Object(r.to_owned()));
                             self.result.push_back(res);
@@ -213,11 +313,90 @@ impl Executor for TiDBNestedLoopExpand {
                 }
             }
         }
+    }
+
+    fn fetch_relationships(&self, conn: &mut Connection) -> Result<Vec<ExecutionContext>, Error> {
+        let res = scan_relationships(
+            &self.context,
+            &self.rel_types,
+            &self.rel_name,
+            &self.rel_filter,
+            &self.params,
+            conn,
+        )?;
+        Ok(res)
+    }
+
+    fn fetch_end_nodes(&self, conn: &mut Connection) -> Result<Vec<ExecutionContext>, Error> {
+        let res = scan_nodes(
+            &self.context,
+            &self.end_labels,
+            &self.end_name,
+            &self.end_filter,
+            &self.params,
+            conn,
+        )?;
+        Ok(res)
+    }
+}
+
+// Relationships and end nodes are fetched once, and paths starting from node
+// of every input row are expanded by DFS
+pub struct TiDBVarLengthExpand {
+    context: Context,
+    session: Option<Session>,
+    params: Params,
+    result: VecDeque<ExecutionContext>,
+    start_name: String,
+    end_name: String,
+    rel_name: String,
+    rel_types: Vec<String>,
+    end_labels: Vec<String>,
+    min_hops: u64,
+    max_hops: Option<u64>,
+    undirected: bool,
+    rel_filter: Vec<ScalarExpr>,
+    end_filter: Vec<ScalarExpr>,
+
+    child: Box<dyn Executor>,
+}
+
+impl Executor for TiDBVarLengthExpand {
+    fn open(&mut self) -> Result<(), Error> {
+        self.child.open()?;
+        // Connection borrows the session, so it's released before expanding
+        let (rel_rows, end_rows) = {
+            let mut conn = prepare_connection(&self.context, &self.session)?;
+            let rel_rows = scan_relationships(
+                &self.context,
+                &self.rel_types,
+                &self.rel_name,
+                &self.rel_filter,
+                &self.params,
+                &mut conn,
+            )?;
+            let end_rows = scan_nodes(
+                &self.context,
+                &self.end_labels,
+                &self.end_name,
+                &self.end_filter,
+                &self.params,
+                &mut conn,
+            )?;
+            (rel_rows, end_rows)
+        };
+        let graph = self.index_graph(rel_rows, end_rows);
+
+        self.result.clear();
+        while let Some(ctx) = self.child.next()? {
+            self.expand_row(&ctx, &graph);
+        }
 
         Ok(())
     }
 
     fn close(&mut self) -> Result<(), Error> {
+        self.result.clear();
         self.child.close()
     }
 
@@ -226,55 +405,272 @@ impl Executor for TiDBNestedLoopExpand {
     }
 }
 
-impl TiDBNestedLoopExpand {
-    pub fn new(child: Box<dyn Executor>, expand: &ExpandExpr) -> TiDBNestedLoopExpand {
-        TiDBNestedLoopExpand {
+// Relationships, relationships indexed by the nodes they are traversed from
+// with ids of nodes on the other side, and end nodes indexed by their ids
+type Graph = (
+    Vec<Object>,
+    HashMap<String, Vec<(usize, String)>>,
+    HashMap<String, Object>,
+);
+
+impl TiDBVarLengthExpand {
+    pub fn new(
+        child: Box<dyn Executor>,
+        expand: &VarLengthExpandExpr,
+        session: Option<Session>,
+        params: &Params,
+    ) -> TiDBVarLengthExpand {
+        TiDBVarLengthExpand {
             context: Context::new(),
+            session: session,
+            params: params.clone(),
             result: VecDeque::new(),
             start_name: expand.start_name.to_owned(),
             end_name: expand.end_name.to_owned(),
             rel_name: expand.rel_name.to_owned(),
-            rel_type: expand.rel_type.to_owned(),
-            end_label: expand.end_label.to_owned(),
+            rel_types: expand.rel_types.to_owned(),
+            end_labels: expand.end_labels.to_owned(),
+            min_hops: expand.min_hops,
+            max_hops: expand.max_hops,
+            undirected: expand.undirected,
+            rel_filter: expand.rel_filter.to_owned(),
+            end_filter: expand.end_filter.to_owned(),
 
             child: child,
         }
     }
 
-    fn fetch_relationships(
+    fn index_graph(
+        &self,
+        rel_rows: Vec<ExecutionContext>,
+        end_rows: Vec<ExecutionContext>,
+    ) -> Graph {
+        let mut rels = Vec::new();
+        let mut adjacency: HashMap<String, Vec<(usize, String)>> = HashMap::new();
+        for ctx in rel_rows.into_iter() {
+            if let Some(Value::Object(rel)) = ctx.get(&self.rel_name) {
+                if let (Some(start), Some(end)) = (rel.get("start"), rel.get("end")) {
+                    let (start, end) = (start.group_key(), end.group_key());
+                    adjacency
+                        .entry(start.to_owned())
+                        .or_default()
+                        .push((rels.len(), end.to_owned()));
+                    // Self loop is traversed only once
+                    if self.undirected && start != end {
+                        adjacency.entry(end).or_default().push((rels.len(), start));
+                    }
+                    rels.push(rel.to_owned());
+                }
+            }
+        }
+        let mut nodes = HashMap::new();
+        for ctx in end_rows.into_iter() {
+            if let Some(Value::Object(node)) = ctx.get(&self.end_name) {
+                if let Some(id) = node.get("id") {
+                    nodes.insert(id.group_key(), node.to_owned());
+                }
+            }
+        }
+        (rels, adjacency, nodes)
+    }
+
+    // Paths starting from node of the input row
+    fn expand_row(&mut self, ctx: &ExecutionContext, graph: &Graph) {
+        let start = match ctx.get(&self.start_name) {
+            Some(Value::Object(start)) => start.get("id").map(|v| v.group_key()),
+            _ => None,
+        };
+        if let Some(start) = start {
+            let (rels, adjacency, nodes) = graph;
+            self.expand(ctx, &start, rels, adjacency, nodes, &mut Vec::new());
+        }
+    }
+
+    // `path` holds indexes of relationships from the start node to `node`
+    fn expand(
         &mut self,
-        conn: &mut PooledConn,
-    ) -> Result<Vec<ExecutionContext>, Error> {
-        let res = scan_table(self.rel_table_name(), &self.rel_name, conn)?;
-        Ok(res)
+        ctx: &ExecutionContext,
+        node: &String,
+        rels: &Vec<Object>,
+        adjacency: &HashMap<String, Vec<(usize, String)>>,
+        nodes: &HashMap<String, Object>,
+        path: &mut Vec<usize>,
+    ) {
+        let hops = path.len() as u64;
+        if hops >= self.min_hops {
+            if let Some(end) = nodes.get(node) {
+                // End node may have been binded by previous clauses
+                let bound = match ctx.get(&self.end_name) {
+                    Some(Value::Object(bound)) => bound.get("id") == end.get("id"),
+                    _ => true,
+                };
+                if bound {
+                    let elements = path.iter().map(|i| Value::Object(rels[*i].to_owned()));
+                    let mut res = ctx.clone();
+                    res.set(
+                        &self.rel_name,
+                        Value::Array(Array {
+                            elements: elements.collect(),
+                        }),
+                    );
+                    res.set(&self.end_name, Value::Object(end.to_owned()));
+                    self.result.push_back(res);
+                }
+            }
+        }
+        if self.max_hops.map_or(false, |max| hops >= max) {
+            return;
+        }
+
+        if let Some(next) = adjacency.get(node) {
+            for (i, other) in next.iter() {
+                // Every relationship appears at most once in a path
+                if path.contains(i) {
+                    continue;
+                }
+                path.push(*i);
+                self.expand(ctx, other, rels, adjacency, nodes, path);
+                path.pop();
+            }
+        }
     }
+}
 
-    fn fetch_end_nodes(&mut self, conn: &mut PooledConn) -> Result<Vec<ExecutionContext>, Error> {
-        let res = scan_table(self.end_node_table_name(), &self.end_name, conn)?;
-        Ok(res)
+// Rows of rhs are collected once, and every row of lhs is joined with them.
+// Variables binded by both sides must refer to the same value.
+pub struct NestedLoopJoin {
+    join_type: JoinType,
+    rhs_rows: Vec<ExecutionContext>,
+    result: VecDeque<ExecutionContext>,
+
+    lhs: Box<dyn Executor>,
+    rhs: Box<dyn Executor>,
+}
+
+impl Executor for NestedLoopJoin {
+    fn open(&mut self) -> Result<(), Error> {
+        self.rhs_rows.clear();
+        self.result.clear();
+        self.rhs.open()?;
+        while let Some(row) = self.rhs.next()? {
+            self.rhs_rows.push(row);
+        }
+        self.rhs.close()?;
+        self.lhs.open()
     }
 
-    fn rel_table_name(&self) -> &String {
-        match self
-            .context
-            .tidb_service
-            .label_table_map
-            .get(&self.rel_type)
-        {
-            Some(name) => name,
-            None => &self.rel_type,
+    fn close(&mut self) -> Result<(), Error> {
+        self.rhs_rows.clear();
+        self.result.clear();
+        self.lhs.close()
+    }
+
+    fn next(&mut self) -> Result<Option<ExecutionContext>, Error> {
+        loop {
+            if let Some(row) = self.result.pop_front() {
+                return Ok(Some(row));
+            }
+
+            let ctx = match self.lhs.next()? {
+                Some(ctx) => ctx,
+                None => return Ok(None),
+            };
+            match self.join_type {
+                JoinType::CartesianProduct => {
+                    for row in self.rhs_rows.iter() {
+                        if let Some(row) = correlate(&ctx, row.clone()) {
+                            self.result.push_back(row);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl NestedLoopJoin {
+    pub fn new(lhs: Box<dyn Executor>, rhs: Box<dyn Executor>, join: &JoinExpr) -> NestedLoopJoin {
+        NestedLoopJoin {
+            join_type: join.join_type.to_owned(),
+            rhs_rows: Vec::new(),
+            result: VecDeque::new(),
+            lhs: lhs,
+            rhs: rhs,
         }
     }
+}
 
-    fn end_node_table_name(&self) -> &String {
-        match self
-            .context
-            .tidb_service
-            .label_table_map
-            .get(&self.end_label)
-        {
-            Some(name) => name,
-            None => &self.end_label,
+// Left outer join of input rows and pattern rows. Rows of pattern are
+// collected once, and joined with every input row on shared variables.
+pub struct NestedLoopLeftOuterApply {
+    filter: Vec<ScalarExpr>,
+    optional_names: Vec<String>,
+    pattern_rows: Vec<ExecutionContext>,
+    result: VecDeque<ExecutionContext>,
+
+    pattern: Box<dyn Executor>,
+    child: Box<dyn Executor>,
+}
+
+impl Executor for NestedLoopLeftOuterApply {
+    fn open(&mut self) -> Result<(), Error> {
+        self.pattern_rows.clear();
+        self.result.clear();
+        self.pattern.open()?;
+        while let Some(row) = self.pattern.next()? {
+            self.pattern_rows.push(row);
+        }
+        self.pattern.close()?;
+        self.child.open()
+    }
+
+    fn close(&mut self) -> Result<(), Error> {
+        self.pattern_rows.clear();
+        self.result.clear();
+        self.child.close()
+    }
+
+    fn next(&mut self) -> Result<Option<ExecutionContext>, Error> {
+        loop {
+            if let Some(row) = self.result.pop_front() {
+                return Ok(Some(row));
+            }
+
+            let ctx = match self.child.next()? {
+                Some(ctx) => ctx,
+                None => return Ok(None),
+            };
+            for row in self.pattern_rows.iter() {
+                if let Some(row) = correlate(&ctx, row.clone()) {
+                    if check_filter(&self.filter, &row)? {
+                        self.result.push_back(row);
+                    }
+                }
+            }
+            if self.result.is_empty() {
+                let mut row = ctx;
+                for name in self.optional_names.iter() {
+                    row.set(name, Value::Null);
+                }
+                self.result.push_back(row);
+            }
+        }
+    }
+}
+
+impl NestedLoopLeftOuterApply {
+    pub fn new(
+        child: Box<dyn Executor>,
+        pattern: Box<dyn Executor>,
+        apply: &LeftOuterApplyExpr,
+    ) -> NestedLoopLeftOuterApply {
+        NestedLoopLeftOuterApply {
+            filter: apply.filter.to_owned(),
+            optional_names: apply.optional_names.to_owned(),
+            pattern_rows: Vec::new(),
+            result: VecDeque::new(),
+            pattern: pattern,
+            child: child,
         }
     }
 }
@@ -302,8 +698,9 @@ impl Executor for FilterExec {
                         break;
                     };
                     // println!("{:#?}", ctx);
+                    // Rows are dropped if predicate is false or null
                     match eval(filter, &ctx)? {
-                        Value::Boolean(v) if v => result = true,
+                        Value::Boolean(true) => result = true,
                         _ => result = false,
                     };
                 }
@@ -327,58 +724,1947 @@ impl FilterExec {
     }
 }
 
-fn prepare_tidb_connection(context: &Context) -> Result<PooledConn, Error> {
-    let TiDBService {
-        ref host,
-        ref port,
-        ref username,
-        ref password,
-        ref database,
-        ..
-    } = context.tidb_service;
+pub struct SingleRowExec {
+    params: Params,
+    done: bool,
+}
 
-    let url = format!(
-        "mysql://{}:{}@{}:{}/{}",
-        username, password, host, port, database,
-    );
+impl Executor for SingleRowExec {
+    fn open(&mut self) -> Result<(), Error> {
+        self.done = false;
+        Ok(())
+    }
 
-    let pool = Pool::new(url)?;
-    Ok(pool.get_conn()?)
+    fn close(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<ExecutionContext>, Error> {
+        if self.done {
+            Ok(None)
+        } else {
+            self.done = true;
+            Ok(Some(ExecutionContext::with_params(&self.params)))
+        }
+    }
 }
 
-fn scan_table(
-    table_name: &String,
-    variable_name: &String,
-    conn: &mut PooledConn,
-) -> Result<Vec<ExecutionContext>, Error> {
-    let sql = format!("select * from {}", table_name);
-    let mut result = conn.query_iter(sql)?;
+impl SingleRowExec {
+    pub fn new(params: &Params) -> SingleRowExec {
+        SingleRowExec {
+            params: params.clone(),
+            done: false,
+        }
+    }
+}
 
-    let mut res = Vec::new();
+// Group rows by hash of grouping keys. Without grouping key, a single group
+// is returned even if there is no input row.
+pub struct HashAggregateExec {
+    group_by: Vec<(ScalarExpr, String)>,
+    aggregates: Vec<AggregateCall>,
+    params: Params,
+    result: VecDeque<ExecutionContext>,
 
-    while let Some(result_set) = result.next_set() {
-        let result_set = result_set?;
+    child: Box<dyn Executor>,
+}
 
-        let column_names: Vec<String> = result_set
-            .columns()
-            .as_ref()
-            .into_iter()
-            .map(|v| String::from_utf8(Vec::from(v.name_ref())).unwrap())
-            .collect();
+impl Executor for HashAggregateExec {
+    fn open(&mut self) -> Result<(), Error> {
+        self.child.open()?;
+        // Groups are kept in order of their first rows
+        let mut groups: Vec<(Vec<Value>, Vec<Accumulator>)> = Vec::new();
+        let mut index = HashMap::new();
+        while let Some(ctx) = self.child.next()? {
+            let mut keys = Vec::new();
+            for (expr, _) in self.group_by.iter() {
+                keys.push(eval(expr, &ctx)?);
+            }
+            let key: Vec<String> = keys.iter().map(|v| v.group_key()).collect();
+            let i = match index.get(&key) {
+                Some(i) => *i,
+                None => {
+                    index.insert(key, groups.len());
+                    groups.push((keys, self.new_accumulators()));
+                    groups.len() - 1
+                }
+            };
+            for (call, acc) in self.aggregates.iter().zip(groups[i].1.iter_mut()) {
+                let mut args = Vec::new();
+                for arg in call.args.iter() {
+                    args.push(eval(arg, &ctx)?);
+                }
+                acc.update(args)?;
+            }
+        }
+        if groups.is_empty() && self.group_by.is_empty() {
+            groups.push((Vec::new(), self.new_accumulators()));
+        }
 
-        for row in result_set {
-            let mut ctx = ExecutionContext::new();
-            let mut obj = Object::new();
-            let mut row = row?;
-            for i in 0..row.len() {
-                // println!("Row: {:#?}", row);
-                let v: Value = row.take(i).unwrap();
-                obj.set(column_names.get(i).unwrap(), &v);
+        self.result.clear();
+        for (keys, accs) in groups.into_iter() {
+            let mut ctx = ExecutionContext::with_params(&self.params);
+            for ((_, alias), key) in self.group_by.iter().zip(keys.into_iter()) {
+                ctx.set(alias, key);
             }
-            ctx.set(variable_name, Value::Object(obj));
-            res.push(ctx);
+            for (call, acc) in self.aggregates.iter().zip(accs.into_iter()) {
+                ctx.set(&call.name, acc.finish()?);
+            }
+            self.result.push_back(ctx);
+        }
+
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), Error> {
+        self.result.clear();
+        self.child.close()
+    }
+
+    fn next(&mut self) -> Result<Option<ExecutionContext>, Error> {
+        Ok(self.result.pop_front())
+    }
+}
+
+impl HashAggregateExec {
+    pub fn new(
+        child: Box<dyn Executor>,
+        expr: &AggregateExpr,
+        params: &Params,
+    ) -> HashAggregateExec {
+        HashAggregateExec {
+            group_by: expr.group_by.to_owned(),
+            aggregates: expr.aggregates.to_owned(),
+            params: params.clone(),
+            result: VecDeque::new(),
+            child: child,
         }
     }
 
-    Ok(res)
+    fn new_accumulators(&self) -> Vec<Accumulator> {
+        self.aggregates
+            .iter()
+            .map(|v| Accumulator::new(&v.func, v.distinct))
+            .collect()
+    }
+}
+
+pub struct SortExec {
+    keys: Vec<SortKey>,
+    result: VecDeque<ExecutionContext>,
+
+    child: Box<dyn Executor>,
+}
+
+impl Executor for SortExec {
+    fn open(&mut self) -> Result<(), Error> {
+        self.child.open()?;
+        let mut rows = Vec::new();
+        while let Some(ctx) = self.child.next()? {
+            let key = eval_sort_key(&self.keys, &ctx)?;
+            rows.push((key, ctx));
+        }
+        rows.sort_by(|l, r| compare_sort_key(&self.keys, &l.0, &r.0));
+        self.result = rows.into_iter().map(|v| v.1).collect();
+
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), Error> {
+        self.result.clear();
+        self.child.close()
+    }
+
+    fn next(&mut self) -> Result<Option<ExecutionContext>, Error> {
+        Ok(self.result.pop_front())
+    }
+}
+
+impl SortExec {
+    pub fn new(child: Box<dyn Executor>, expr: &SortExpr) -> SortExec {
+        SortExec {
+            keys: expr.keys.to_owned(),
+            result: VecDeque::new(),
+            child: child,
+        }
+    }
+}
+
+// Sort rows while keeping only the first `skip + limit` ones, and then skip
+// the first `skip` rows of them.
+pub struct TopNExec {
+    keys: Vec<SortKey>,
+    skip: Option<ScalarExpr>,
+    limit: ScalarExpr,
+    params: Params,
+    result: VecDeque<ExecutionContext>,
+
+    child: Box<dyn Executor>,
+}
+
+impl Executor for TopNExec {
+    fn open(&mut self) -> Result<(), Error> {
+        let skip = match self.skip.as_ref() {
+            Some(skip) => eval_row_count(skip, &self.params)?,
+            None => 0,
+        };
+        let limit = eval_row_count(&self.limit, &self.params)?;
+        let size = skip + limit;
+
+        self.child.open()?;
+        let mut rows: Vec<(Vec<Value>, ExecutionContext)> = Vec::new();
+        while let Some(ctx) = self.child.next()? {
+            if size == 0 {
+                continue;
+            }
+            let key = eval_sort_key(&self.keys, &ctx)?;
+            // Insert after all equal keys to keep the sort stable
+            let pos = rows
+                .iter()
+                .position(|v| compare_sort_key(&self.keys, &key, &v.0) == Ordering::Less)
+                .unwrap_or(rows.len());
+            if pos < size {
+                rows.insert(pos, (key, ctx));
+                rows.truncate(size);
+            }
+        }
+        self.result = rows.into_iter().skip(skip).map(|v| v.1).collect();
+
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), Error> {
+        self.result.clear();
+        self.child.close()
+    }
+
+    fn next(&mut self) -> Result<Option<ExecutionContext>, Error> {
+        Ok(self.result.pop_front())
+    }
+}
+
+impl TopNExec {
+    pub fn new(child: Box<dyn Executor>, expr: &TopNExpr, params: &Params) -> TopNExec {
+        TopNExec {
+            keys: expr.keys.to_owned(),
+            skip: expr.skip.to_owned(),
+            limit: expr.limit.to_owned(),
+            params: params.clone(),
+            result: VecDeque::new(),
+            child: child,
+        }
+    }
+}
+
+pub struct LimitExec {
+    skip: Option<ScalarExpr>,
+    limit: Option<ScalarExpr>,
+    params: Params,
+    // Rows remained to be skipped and returned
+    skip_count: usize,
+    limit_count: Option<usize>,
+
+    child: Box<dyn Executor>,
+}
+
+impl Executor for LimitExec {
+    fn open(&mut self) -> Result<(), Error> {
+        self.skip_count = match self.skip.as_ref() {
+            Some(skip) => eval_row_count(skip, &self.params)?,
+            None => 0,
+        };
+        self.limit_count = match self.limit.as_ref() {
+            Some(limit) => Some(eval_row_count(limit, &self.params)?),
+            None => None,
+        };
+        self.child.open()
+    }
+
+    fn close(&mut self) -> Result<(), Error> {
+        self.child.close()
+    }
+
+    fn next(&mut self) -> Result<Option<ExecutionContext>, Error> {
+        if self.limit_count == Some(0) {
+            return Ok(None);
+        }
+        while self.skip_count > 0 {
+            if self.child.next()?.is_none() {
+                return Ok(None);
+            }
+            self.skip_count -= 1;
+        }
+        match self.child.next()? {
+            Some(ctx) => {
+                self.limit_count = self.limit_count.map(|v| v - 1);
+                Ok(Some(ctx))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl LimitExec {
+    pub fn new(child: Box<dyn Executor>, expr: &LimitExpr, params: &Params) -> LimitExec {
+        LimitExec {
+            skip: expr.skip.to_owned(),
+            limit: expr.limit.to_owned(),
+            params: params.clone(),
+            skip_count: 0,
+            limit_count: None,
+            child: child,
+        }
+    }
+}
+
+// Bind every element of list to a new row. Null produces no row, and other
+// values are treated as lists with single element.
+pub struct UnwindExec {
+    expr: ScalarExpr,
+    name: String,
+    input: Option<ExecutionContext>,
+    elements: VecDeque<Value>,
+
+    child: Box<dyn Executor>,
+}
+
+impl Executor for UnwindExec {
+    fn open(&mut self) -> Result<(), Error> {
+        self.input = None;
+        self.elements.clear();
+        self.child.open()
+    }
+
+    fn close(&mut self) -> Result<(), Error> {
+        self.input = None;
+        self.elements.clear();
+        self.child.close()
+    }
+
+    fn next(&mut self) -> Result<Option<ExecutionContext>, Error> {
+        loop {
+            if let Some(element) = self.elements.pop_front() {
+                let mut ctx = self.input.as_ref().unwrap().clone();
+                ctx.set(&self.name, element);
+                return Ok(Some(ctx));
+            }
+
+            match self.child.next()? {
+                Some(ctx) => {
+                    self.elements = match eval(&self.expr, &ctx)? {
+                        Value::Array(array) => array.elements.into_iter().collect(),
+                        Value::Null => VecDeque::new(),
+                        v @ _ => VecDeque::from(vec![v]),
+                    };
+                    self.input = Some(ctx);
+                }
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+impl UnwindExec {
+    pub fn new(child: Box<dyn Executor>, expr: &UnwindExpr) -> UnwindExec {
+        UnwindExec {
+            expr: expr.expr.to_owned(),
+            name: expr.name.to_owned(),
+            input: None,
+            elements: VecDeque::new(),
+            child: child,
+        }
+    }
+}
+
+// Children are executed one after another. For UNION, keys of rows already
+// returned are remembered to remove duplicates.
+pub struct UnionExec {
+    all: bool,
+    // Index of the child being executed
+    current: usize,
+    returned: HashSet<Vec<(String, String)>>,
+
+    children: Vec<Box<dyn Executor>>,
+}
+
+impl Executor for UnionExec {
+    fn open(&mut self) -> Result<(), Error> {
+        self.current = 0;
+        self.returned.clear();
+        self.children[0].open()
+    }
+
+    fn close(&mut self) -> Result<(), Error> {
+        self.returned.clear();
+        if self.current < self.children.len() {
+            self.children[self.current].close()?;
+        }
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<ExecutionContext>, Error> {
+        while self.current < self.children.len() {
+            match self.children[self.current].next()? {
+                Some(ctx) => {
+                    if self.all {
+                        return Ok(Some(ctx));
+                    }
+                    if self.returned.insert(row_key(&ctx)) {
+                        return Ok(Some(ctx));
+                    }
+                }
+                None => {
+                    self.children[self.current].close()?;
+                    self.current += 1;
+                    if self.current < self.children.len() {
+                        self.children[self.current].open()?;
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl UnionExec {
+    pub fn new(children: Vec<Box<dyn Executor>>, expr: &UnionExpr) -> UnionExec {
+        UnionExec {
+            all: expr.all,
+            current: 0,
+            returned: HashSet::new(),
+            children: children,
+        }
+    }
+}
+
+// Rows binding equal values to the same variables have the same key
+fn row_key(ctx: &ExecutionContext) -> Vec<(String, String)> {
+    let mut key: Vec<(String, String)> = ctx
+        .values
+        .iter()
+        .map(|(name, value)| (name.to_owned(), value.group_key()))
+        .collect();
+    key.sort();
+    key
+}
+
+fn eval_sort_key(keys: &Vec<SortKey>, ctx: &ExecutionContext) -> Result<Vec<Value>, Error> {
+    let mut result = Vec::new();
+    for key in keys.iter() {
+        result.push(eval(&key.expr, ctx)?);
+    }
+    Ok(result)
+}
+
+fn compare_sort_key(keys: &Vec<SortKey>, l: &Vec<Value>, r: &Vec<Value>) -> Ordering {
+    for (i, key) in keys.iter().enumerate() {
+        let ord = l[i].order(&r[i]);
+        let ord = if key.descending { ord.reverse() } else { ord };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    Ordering::Equal
+}
+
+// Evaluate row count of SKIP or LIMIT, which must be a non-negative integer
+fn eval_row_count(expr: &ScalarExpr, params: &Params) -> Result<usize, Error> {
+    match eval(expr, &ExecutionContext::with_params(params))? {
+        Value::Int(v) if v >= 0 => Ok(v as usize),
+        Value::Double(v) if v >= 0.0 && v.fract() == 0.0 => Ok(v as usize),
+        v @ _ => Err(Error {
+            msg: format!("invalid row count: {:?}", v),
+            kind: ErrorKind::Semantic,
+        }),
+    }
+}
+
+// Insert nodes and relationships into label tables for every input row.
+// Created entities are binded to their variables with `id` filled, and
+// relationships reference their endpoints with `start` and `end` columns.
+pub struct TiDBCreateExec {
+    context: Context,
+    result: VecDeque<ExecutionContext>,
+    nodes: Vec<CreateNode>,
+    rels: Vec<CreateRel>,
+
+    child: Box<dyn Executor>,
+}
+
+impl Executor for TiDBCreateExec {
+    fn open(&mut self) -> Result<(), Error> {
+        self.child.open()?;
+        let mut conn = prepare_tidb_connection(&self.context)?;
+        prepare_schema(&self.context, &mut conn)?;
+        conn.query_drop("begin")?;
+        match self.create(&mut conn) {
+            Ok(_) => conn.query_drop("commit")?,
+            Err(err) => {
+                conn.query_drop("rollback")?;
+                self.result.clear();
+                return Err(err);
+            }
+        };
+
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), Error> {
+        self.child.close()
+    }
+
+    fn next(&mut self) -> Result<Option<ExecutionContext>, Error> {
+        Ok(self.result.pop_front())
+    }
+}
+
+impl TiDBCreateExec {
+    pub fn new(child: Box<dyn Executor>, create: &CreateExpr) -> TiDBCreateExec {
+        TiDBCreateExec {
+            context: Context::new(),
+            result: VecDeque::new(),
+            nodes: create.nodes.to_owned(),
+            rels: create.rels.to_owned(),
+
+            child: child,
+        }
+    }
+
+    fn create(&mut self, conn: &mut PooledConn) -> Result<(), Error> {
+        while let Some(mut ctx) = self.child.next()? {
+            create_entities(&self.context, &self.nodes, &self.rels, &mut ctx, conn)?;
+            self.result.push_back(ctx);
+        }
+        Ok(())
+    }
+}
+
+// Match pattern for every input row, and create entities of the pattern if
+// nothing matches. Matching and creating of all rows run in one transaction.
+// TiDB has no gap lock, so locking matched rows can't stop a concurrent MERGE
+// from creating the same entities. Instead, a lock row of every table to
+// create entities in is held until the transaction ends.
+pub struct TiDBMergeExec {
+    context: Context,
+    session: Session,
+    result: VecDeque<ExecutionContext>,
+    filter: Vec<ScalarExpr>,
+    nodes: Vec<CreateNode>,
+    rels: Vec<CreateRel>,
+    on_match: Vec<UpdateItem>,
+    on_create: Vec<UpdateItem>,
+
+    pattern: Box<dyn Executor>,
+    child: Box<dyn Executor>,
+}
+
+impl Executor for TiDBMergeExec {
+    fn open(&mut self) -> Result<(), Error> {
+        self.child.open()?;
+        let mut conn = prepare_tidb_connection(&self.context)?;
+        prepare_schema(&self.context, &mut conn)?;
+        conn.query_drop("begin pessimistic")?;
+        *self.session.borrow_mut() = Some(conn);
+
+        let res = self.lock_tables().and_then(|_| self.merge());
+
+        let mut conn = self.session.borrow_mut().take().unwrap();
+        match res {
+            Ok(_) => conn.query_drop("commit")?,
+            Err(err) => {
+                conn.query_drop("rollback")?;
+                return Err(err);
+            }
+        };
+
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), Error> {
+        self.child.close()
+    }
+
+    fn next(&mut self) -> Result<Option<ExecutionContext>, Error> {
+        Ok(self.result.pop_front())
+    }
+}
+
+impl TiDBMergeExec {
+    pub fn new(
+        child: Box<dyn Executor>,
+        pattern: Box<dyn Executor>,
+        merge: &MergeExpr,
+        session: Session,
+    ) -> TiDBMergeExec {
+        TiDBMergeExec {
+            context: Context::new(),
+            session: session,
+            result: VecDeque::new(),
+            filter: merge.filter.to_owned(),
+            nodes: merge.nodes.to_owned(),
+            rels: merge.rels.to_owned(),
+            on_match: merge.on_match.to_owned(),
+            on_create: merge.on_create.to_owned(),
+
+            pattern: pattern,
+            child: child,
+        }
+    }
+
+    // Tables are locked in order to avoid deadlock
+    fn lock_tables(&mut self) -> Result<(), Error> {
+        let mut tables: Vec<String> = self
+            .nodes
+            .iter()
+            .map(|v| resolve_table_name(&self.context, &v.label))
+            .chain(
+                self.rels
+                    .iter()
+                    .map(|v| resolve_table_name(&self.context, &v.rel_type)),
+            )
+            .collect();
+        tables.sort();
+        tables.dedup();
+
+        let mut session = self.session.borrow_mut();
+        let conn = session.as_mut().unwrap();
+        for table_name in tables.into_iter() {
+            // Lock row is created by the first MERGE on the table, and
+            // concurrent ones wait for its key
+            conn.exec_drop(
+                format!("insert ignore into {} (name) values (?)", LOCK_TABLE),
+                (table_name.to_owned(),),
+            )?;
+            conn.exec_drop(
+                format!("select name from {} where name = ? for update", LOCK_TABLE),
+                (table_name,),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self) -> Result<(), Error> {
+        // Columns of tables are loaded once for updates of all rows
+        let schema = {
+            let mut session = self.session.borrow_mut();
+            Schema::load(&self.context, session.as_mut().unwrap())?
+        };
+        while let Some(ctx) = self.child.next()? {
+            // Pattern is matched again for every row, so that entities created
+            // by previous rows are visible.
+            let mut matched = Vec::new();
+            self.pattern.open()?;
+            while let Some(row) = self.pattern.next()? {
+                if let Some(row) = correlate(&ctx, row) {
+                    if check_filter(&self.filter, &row)? {
+                        matched.push(row);
+                    }
+                }
+            }
+            self.pattern.close()?;
+
+            let mut session = self.session.borrow_mut();
+            let conn = session.as_mut().unwrap();
+            if matched.is_empty() {
+                let mut row = ctx;
+                create_entities(&self.context, &self.nodes, &self.rels, &mut row, conn)?;
+                apply_updates(&self.context, &schema, &self.on_create, &mut row, conn)?;
+                self.result.push_back(row);
+            } else {
+                for mut row in matched.into_iter() {
+                    apply_updates(&self.context, &schema, &self.on_match, &mut row, conn)?;
+                    self.result.push_back(row);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Apply SET and REMOVE items to entities of every input row. Input rows are
+// matched and updated in one transaction, whose connection is shared with the
+// child, so matched rows are locked until they are updated.
+pub struct TiDBUpdateExec {
+    context: Context,
+    session: Session,
+    result: VecDeque<ExecutionContext>,
+    items: Vec<UpdateItem>,
+
+    child: Box<dyn Executor>,
+}
+
+impl Executor for TiDBUpdateExec {
+    fn open(&mut self) -> Result<(), Error> {
+        let mut conn = prepare_tidb_connection(&self.context)?;
+        conn.query_drop("begin pessimistic")?;
+        *self.session.borrow_mut() = Some(conn);
+
+        let res = self.child.open().and_then(|_| self.update());
+
+        let mut conn = self.session.borrow_mut().take().unwrap();
+        match res {
+            Ok(_) => conn.query_drop("commit")?,
+            Err(err) => {
+                conn.query_drop("rollback")?;
+                self.result.clear();
+                return Err(err);
+            }
+        };
+
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), Error> {
+        self.child.close()
+    }
+
+    fn next(&mut self) -> Result<Option<ExecutionContext>, Error> {
+        Ok(self.result.pop_front())
+    }
+}
+
+impl TiDBUpdateExec {
+    pub fn new(child: Box<dyn Executor>, update: &UpdateExpr, session: Session) -> TiDBUpdateExec {
+        TiDBUpdateExec {
+            context: Context::new(),
+            session: session,
+            result: VecDeque::new(),
+            items: update.items.to_owned(),
+
+            child: child,
+        }
+    }
+
+    fn update(&mut self) -> Result<(), Error> {
+        let schema = {
+            let mut session = self.session.borrow_mut();
+            Schema::load(&self.context, session.as_mut().unwrap())?
+        };
+        // Session is borrowed by the child when fetching the next row
+        while let Some(mut ctx) = self.child.next()? {
+            let mut session = self.session.borrow_mut();
+            let conn = session.as_mut().unwrap();
+            apply_updates(&self.context, &schema, &self.items, &mut ctx, conn)?;
+            self.result.push_back(ctx);
+        }
+        Ok(())
+    }
+}
+
+// Delete nodes and relationships binded by input rows in one transaction.
+// Relationships are deleted before nodes, so a node can be deleted along with
+// all of its relationships.
+pub struct TiDBDeleteExec {
+    context: Context,
+    result: VecDeque<ExecutionContext>,
+    items: Vec<DeleteItem>,
+    detach: bool,
+
+    child: Box<dyn Executor>,
+}
+
+impl Executor for TiDBDeleteExec {
+    fn open(&mut self) -> Result<(), Error> {
+        self.child.open()?;
+        while let Some(ctx) = self.child.next()? {
+            self.result.push_back(ctx);
+        }
+
+        let mut conn = prepare_tidb_connection(&self.context)?;
+        conn.query_drop("begin")?;
+        match self.delete(&mut conn) {
+            Ok(_) => conn.query_drop("commit")?,
+            Err(err) => {
+                conn.query_drop("rollback")?;
+                return Err(err);
+            }
+        };
+
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), Error> {
+        self.child.close()
+    }
+
+    fn next(&mut self) -> Result<Option<ExecutionContext>, Error> {
+        Ok(self.result.pop_front())
+    }
+}
+
+impl TiDBDeleteExec {
+    pub fn new(child: Box<dyn Executor>, delete: &DeleteExpr) -> TiDBDeleteExec {
+        TiDBDeleteExec {
+            context: Context::new(),
+            result: VecDeque::new(),
+            items: delete.items.to_owned(),
+            detach: delete.detach,
+
+            child: child,
+        }
+    }
+
+    // Ids are unique across tables, so entities are deleted from every table
+    // of nodes or relationships by their ids, and relationships of a node are
+    // matched by its id. Deleting null does nothing.
+    fn delete(&self, conn: &mut PooledConn) -> Result<(), Error> {
+        let rel_tables = entity_tables(&self.context, true, conn)?;
+        let node_tables = entity_tables(&self.context, false, conn)?;
+        for item in self.items.iter().filter(|v| v.relationship) {
+            for ctx in self.result.iter() {
+                if ctx.get(&item.name) == Some(&Value::Null) {
+                    continue;
+                }
+                let id = mysql::Value::from(entity_id(ctx, &item.name)?);
+                for table_name in rel_tables.iter() {
+                    let sql = format!("delete from {} where id = ?", table_name);
+                    conn.exec_drop(sql, (id.to_owned(),))?;
+                }
+            }
+        }
+
+        for item in self.items.iter().filter(|v| !v.relationship) {
+            for ctx in self.result.iter() {
+                if ctx.get(&item.name) == Some(&Value::Null) {
+                    continue;
+                }
+                let id = mysql::Value::from(entity_id(ctx, &item.name)?);
+                for table_name in rel_tables.iter() {
+                    if self.detach {
+                        let sql =
+                            format!("delete from {} where `start` = ? or `end` = ?", table_name);
+                        conn.exec_drop(sql, (id.to_owned(), id.to_owned()))?;
+                    } else {
+                        let sql = format!(
+                            "select count(*) from {} where `start` = ? or `end` = ?",
+                            table_name
+                        );
+                        let count: Option<i64> =
+                            conn.exec_first(sql, (id.to_owned(), id.to_owned()))?;
+                        if count.unwrap_or(0) > 0 {
+                            return Err(Error {
+                                msg: format!(
+                                    "cannot delete node `{}` which still has relationships, use DETACH DELETE instead",
+                                    item.name
+                                ),
+                                kind: ErrorKind::Semantic,
+                            });
+                        }
+                    }
+                }
+                for table_name in node_tables.iter() {
+                    let sql = format!("delete from {} where id = ?", table_name);
+                    conn.exec_drop(sql, (id.to_owned(),))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Names and types of columns, types are the `column_type` of information schema
+fn table_columns(
+    context: &Context,
+    table_name: &String,
+    conn: &mut PooledConn,
+) -> Result<Vec<(String, String)>, Error> {
+    let sql = r#"select column_name, column_type from information_schema.columns
+        where table_schema = ? and table_name = ?"#;
+    let columns: Vec<(String, String)> = conn.exec(
+        sql,
+        (
+            context.tidb_service.database.to_owned(),
+            table_name.to_owned(),
+        ),
+    )?;
+    Ok(columns)
+}
+
+// Tables of nodes or relationships, which are the tables with `id` column
+// except tables of tigraph itself. Tables of relationships are the ones with
+// `start` and `end` columns.
+fn entity_tables(
+    context: &Context,
+    relationship: bool,
+    conn: &mut PooledConn,
+) -> Result<Vec<String>, Error> {
+    let schema = Schema::load(context, conn)?;
+    Ok(schema.tables(relationship).to_owned())
+}
+
+// Columns of tables of nodes and relationships, which are loaded once by
+// executors updating many rows.
+struct Schema {
+    columns: HashMap<String, Vec<String>>,
+    node_tables: Vec<String>,
+    rel_tables: Vec<String>,
+}
+
+impl Schema {
+    fn load(context: &Context, conn: &mut PooledConn) -> Result<Schema, Error> {
+        let sql = r#"select table_name, column_name from information_schema.columns
+            where table_schema = ? and table_name not in (?, ?)
+            order by table_name, ordinal_position"#;
+        let rows: Vec<(String, String)> = conn.exec(
+            sql,
+            (
+                context.tidb_service.database.to_owned(),
+                ID_SEQUENCE,
+                LOCK_TABLE,
+            ),
+        )?;
+
+        let mut columns: HashMap<String, Vec<String>> = HashMap::new();
+        for (table_name, column_name) in rows.into_iter() {
+            columns.entry(table_name).or_default().push(column_name);
+        }
+
+        let mut node_tables = Vec::new();
+        let mut rel_tables = Vec::new();
+        for (table_name, columns) in columns.iter() {
+            let has = |name: &str| columns.iter().any(|v| v == name);
+            if !has("id") {
+                continue;
+            }
+            if has("start") && has("end") {
+                rel_tables.push(table_name.to_owned());
+            } else if !has("start") && !has("end") {
+                node_tables.push(table_name.to_owned());
+            }
+        }
+        node_tables.sort();
+        rel_tables.sort();
+
+        Ok(Schema {
+            columns: columns,
+            node_tables: node_tables,
+            rel_tables: rel_tables,
+        })
+    }
+
+    fn columns(&self, table_name: &String) -> &[String] {
+        match self.columns.get(table_name) {
+            Some(columns) => columns,
+            None => &[],
+        }
+    }
+
+    fn tables(&self, relationship: bool) -> &Vec<String> {
+        if relationship {
+            &self.rel_tables
+        } else {
+            &self.node_tables
+        }
+    }
+}
+
+// Scan nodes having all of the labels, or nodes of any label if there is no
+// label. Node with multiple labels has a row with the same id in table of
+// every label.
+fn scan_nodes(
+    context: &Context,
+    labels: &Vec<String>,
+    variable_name: &String,
+    filter: &Vec<ScalarExpr>,
+    params: &Params,
+    conn: &mut Connection,
+) -> Result<Vec<ExecutionContext>, Error> {
+    if let Some((label, others)) = labels.split_first() {
+        let conditions = others
+            .iter()
+            .map(|label| {
+                format!(
+                    "id in (select id from {})",
+                    resolve_table_name(context, label)
+                )
+            })
+            .collect();
+        return scan_table_with_filter(
+            context,
+            &resolve_table_name(context, label),
+            variable_name,
+            &conditions,
+            filter,
+            params,
+            conn,
+        );
+    }
+
+    // Rows of the same node are merged, so filter is only checked after
+    // scanning all the tables. Rows with the same id belong to the same node.
+    let mut rows = Vec::new();
+    for table_name in entity_tables(context, false, conn)?.iter() {
+        for ctx in scan_table(table_name, variable_name, &Vec::new(), conn)?.into_iter() {
+            if let Some(Value::Object(obj)) = ctx.get(variable_name) {
+                rows.push(obj.to_owned());
+            }
+        }
+    }
+
+    let mut res = Vec::new();
+    for node in merge_node_rows(rows).into_iter() {
+        let mut ctx = ExecutionContext::with_params(params);
+        ctx.set(variable_name, Value::Object(node));
+        if check_filter(filter, &ctx)? {
+            res.push(ctx);
+        }
+    }
+    Ok(res)
+}
+
+// Rows are merged by id only, it's an invariant that an id is never shared by
+// different entities, see `ID_SEQUENCE`.
+fn merge_node_rows(rows: Vec<Object>) -> Vec<Object> {
+    let mut nodes: Vec<Object> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for obj in rows.into_iter() {
+        let key = obj.get("id").map(|id| id.group_key()).unwrap_or_default();
+        match index.get(&key) {
+            Some(i) => {
+                let node: &mut Object = &mut nodes[*i];
+                for (k, v) in obj.props.into_iter() {
+                    node.props.entry(k).or_insert(v);
+                }
+            }
+            None => {
+                index.insert(key, nodes.len());
+                nodes.push(obj);
+            }
+        }
+    }
+    nodes
+}
+
+// Scan relationships of any of the types, or of every type if there is no type
+fn scan_relationships(
+    context: &Context,
+    types: &Vec<String>,
+    variable_name: &String,
+    filter: &Vec<ScalarExpr>,
+    params: &Params,
+    conn: &mut Connection,
+) -> Result<Vec<ExecutionContext>, Error> {
+    let tables = if types.is_empty() {
+        entity_tables(context, true, conn)?
+    } else {
+        types
+            .iter()
+            .map(|rel_type| resolve_table_name(context, rel_type))
+            .collect()
+    };
+
+    let mut res = Vec::new();
+    for table_name in tables.iter() {
+        res.extend(scan_table_with_filter(
+            context,
+            table_name,
+            variable_name,
+            &Vec::new(),
+            filter,
+            params,
+            conn,
+        )?);
+    }
+    Ok(res)
+}
+
+fn check_filter(filter: &Vec<ScalarExpr>, ctx: &ExecutionContext) -> Result<bool, Error> {
+    for filter in filter.iter() {
+        match eval(filter, ctx)? {
+            Value::Boolean(true) => {}
+            _ => return Ok(false),
+        }
+    }
+    Ok(true)
+}
+
+// Combine matched row with input row. Returns None if they bind the same
+// variable to different entities.
+fn correlate(ctx: &ExecutionContext, row: ExecutionContext) -> Option<ExecutionContext> {
+    let mut res = ctx.clone();
+    for (name, value) in row.values.into_iter() {
+        match (ctx.get(&name), &value) {
+            (Some(Value::Object(l)), Value::Object(r)) => {
+                if l.get("id") != r.get("id") {
+                    return None;
+                }
+            }
+            (Some(l), r) => {
+                if l != r {
+                    return None;
+                }
+            }
+            (None, _) => res.set(&name, value),
+        }
+    }
+    Some(res)
+}
+
+fn create_entities(
+    context: &Context,
+    nodes: &Vec<CreateNode>,
+    rels: &Vec<CreateRel>,
+    ctx: &mut ExecutionContext,
+    conn: &mut PooledConn,
+) -> Result<(), Error> {
+    for node in nodes.iter() {
+        let mut obj = Object::new_entity();
+        for (key, value) in node.properties.iter() {
+            obj.set(key, &eval(value, ctx)?);
+        }
+        let table_name = resolve_table_name(context, &node.label);
+        insert_row(&table_name, &mut obj, conn)?;
+        ctx.set(&node.name, Value::Object(obj));
+    }
+
+    for rel in rels.iter() {
+        let mut obj = Object::new_entity();
+        for (key, value) in rel.properties.iter() {
+            obj.set(key, &eval(value, ctx)?);
+        }
+        obj.set("start", &entity_id(ctx, &rel.start_name)?);
+        obj.set("end", &entity_id(ctx, &rel.end_name)?);
+        let table_name = resolve_table_name(context, &rel.rel_type);
+        insert_row(&table_name, &mut obj, conn)?;
+        if let Some(name) = rel.name.as_ref() {
+            ctx.set(name, Value::Object(obj));
+        }
+    }
+
+    Ok(())
+}
+
+// Update rows of entities binded in ctx, keyed by their ids. Updating null,
+// which is binded by OPTIONAL MATCH, does nothing.
+fn apply_updates(
+    context: &Context,
+    schema: &Schema,
+    items: &Vec<UpdateItem>,
+    ctx: &mut ExecutionContext,
+    conn: &mut PooledConn,
+) -> Result<(), Error> {
+    for item in items.iter() {
+        if ctx.get(item.name()) == Some(&Value::Null) {
+            continue;
+        }
+        match item {
+            UpdateItem::Property { name, key, value } => {
+                let value = eval(value, ctx)?;
+                update_row(schema, ctx, name, vec![(key.to_owned(), value)], conn)?;
+            }
+            UpdateItem::Properties {
+                name,
+                properties,
+                replace,
+            } => {
+                let mut assignments = Vec::new();
+                for (key, value) in properties.iter() {
+                    assignments.push((key.to_owned(), eval(value, ctx)?));
+                }
+                if *replace {
+                    // Properties not in map are removed, except columns
+                    // identifying the entity
+                    if let Some(Value::Object(obj)) = ctx.get(name) {
+                        for key in obj.props.keys() {
+                            if !["id", "start", "end"].contains(&key.as_str())
+                                && !properties.iter().any(|v| &v.0 == key)
+                            {
+                                assignments.push((key.to_owned(), Value::Null));
+                            }
+                        }
+                    }
+                }
+                update_row(schema, ctx, name, assignments, conn)?;
+            }
+            UpdateItem::RemoveProperty { name, key } => {
+                update_row(schema, ctx, name, vec![(key.to_owned(), Value::Null)], conn)?;
+            }
+            UpdateItem::AddLabels { name, labels } => {
+                // Node with multiple labels has a row with the same id in
+                // table of every label
+                let obj = match ctx.get(name) {
+                    Some(Value::Object(obj)) => obj.to_owned(),
+                    _ => {
+                        return Err(Error {
+                            msg: format!("variable `{}` is not a node", name),
+                            kind: ErrorKind::Internal,
+                        })
+                    }
+                };
+                let id = entity_id(ctx, name)?;
+                let tables = entity_rows(schema.tables(false), &id, conn)?;
+                for label in labels.iter() {
+                    let table_name = resolve_table_name(context, label);
+                    if tables.contains(&table_name) {
+                        continue;
+                    }
+                    // Only properties having columns in the table are copied
+                    let columns = schema.columns(&table_name);
+                    let mut row = Object::new();
+                    for (key, value) in obj.props.iter() {
+                        if columns.contains(key) {
+                            row.set(key, value);
+                        }
+                    }
+                    insert_row(&table_name, &mut row, conn)?;
+                }
+            }
+            UpdateItem::RemoveLabels { name, labels } => {
+                let id = entity_id(ctx, name)?;
+                for label in labels.iter() {
+                    let sql = format!(
+                        "delete from {} where id = ?",
+                        resolve_table_name(context, label)
+                    );
+                    conn.exec_drop(sql, (mysql::Value::from(id.to_owned()),))?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Update properties of entity binded to `name` in its tables and in ctx.
+// Node with multiple labels has a row in table of every label, and property
+// is updated in every table having its column. Property without column in
+// any of the tables can only be removed.
+fn update_row(
+    schema: &Schema,
+    ctx: &mut ExecutionContext,
+    name: &String,
+    assignments: Vec<(String, Value)>,
+    conn: &mut PooledConn,
+) -> Result<(), Error> {
+    if assignments.is_empty() {
+        return Ok(());
+    }
+
+    let id = entity_id(ctx, name)?;
+    // Relationships are identified by `start` and `end`
+    let relationship = match ctx.get(name) {
+        Some(Value::Object(obj)) => obj.get("start").is_some() && obj.get("end").is_some(),
+        _ => false,
+    };
+    let tables = entity_rows(schema.tables(relationship), &id, conn)?;
+
+    for (key, value) in assignments.iter() {
+        if *value != Value::Null && !tables.iter().any(|v| schema.columns(v).contains(key)) {
+            return Err(Error {
+                msg: format!(
+                    "cannot set property `{}` of `{}`, which has no column `{}` in tables {:?}",
+                    key, name, key, tables
+                ),
+                kind: ErrorKind::Semantic,
+            });
+        }
+    }
+
+    for table_name in tables.iter() {
+        let columns_of_table = schema.columns(table_name);
+        let mut columns = Vec::new();
+        let mut params = Vec::new();
+        for (key, value) in assignments.iter() {
+            if columns_of_table.contains(key) {
+                columns.push(format!("`{}` = ?", key));
+                params.push(mysql::Value::from(value.to_owned()));
+            }
+        }
+        if columns.is_empty() {
+            continue;
+        }
+        params.push(mysql::Value::from(id.to_owned()));
+        let sql = format!(
+            "update {} set {} where id = ?",
+            table_name,
+            columns.join(", ")
+        );
+        conn.exec_drop(sql, params)?;
+    }
+
+    if let Some(Value::Object(obj)) = ctx.values.get_mut(name) {
+        for (key, value) in assignments.iter() {
+            obj.set(key, value);
+        }
+    }
+
+    Ok(())
+}
+
+// Tables having row of the entity, which are found by one query
+fn entity_rows(
+    tables: &Vec<String>,
+    id: &Value,
+    conn: &mut PooledConn,
+) -> Result<Vec<String>, Error> {
+    if tables.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut queries = Vec::new();
+    let mut params = Vec::new();
+    for table_name in tables.iter() {
+        queries.push(format!("select ? from {} where id = ?", table_name));
+        params.push(mysql::Value::from(table_name.to_owned()));
+        params.push(mysql::Value::from(id.to_owned()));
+    }
+    let tables: Vec<String> = conn.exec(queries.join(" union all "), params)?;
+    Ok(tables)
+}
+
+fn resolve_table_name(context: &Context, label: &String) -> String {
+    match context.tidb_service.label_table_map.get(label) {
+        Some(name) => name.to_owned(),
+        None => label.to_owned(),
+    }
+}
+
+// Get id of node or relationship binded to variable
+fn entity_id(ctx: &ExecutionContext, name: &String) -> Result<Value, Error> {
+    match ctx.get(name) {
+        Some(Value::Object(obj)) => match obj.get("id") {
+            Some(id) => Ok(id.to_owned()),
+            None => Err(Error {
+                msg: format!("entity `{}` has no id", name),
+                kind: ErrorKind::Internal,
+            }),
+        },
+        _ => Err(Error {
+            msg: format!("variable `{}` is not a node or relationship", name),
+            kind: ErrorKind::Internal,
+        }),
+    }
+}
+
+// Insert object as a row of table. If `id` is not specified, it's allocated
+// from the shared sequence.
+fn insert_row(table_name: &String, obj: &mut Object, conn: &mut PooledConn) -> Result<(), Error> {
+    if obj.get("id").is_none() {
+        obj.set("id", &next_id(conn)?);
+    }
+    let mut columns = Vec::new();
+    let mut params = Vec::new();
+    for (k, v) in obj.props.iter() {
+        columns.push(format!("`{}`", k));
+        params.push(mysql::Value::from(v.to_owned()));
+    }
+    let sql = format!(
+        "insert into {} ({}) values ({})",
+        table_name,
+        columns.join(", "),
+        vec!["?"; params.len()].join(", "),
+    );
+    conn.exec_drop(sql, params)?;
+
+    Ok(())
+}
+
+// Ids of nodes and relationships are allocated from one sequence, so that an
+// id identifies an entity across all the tables. It's an invariant that rows
+// of different entities never share an id, and rows imported into label tables
+// must take their ids from the sequence too. Rows with the same id in tables
+// of different labels are rows of the same node.
+const ID_SEQUENCE: &str = "tigraph_id";
+
+// Table of rows locked by MERGE, keyed by table names
+const LOCK_TABLE: &str = "tigraph_lock";
+
+fn next_id(conn: &mut PooledConn) -> Result<Value, Error> {
+    let id: Option<i64> = conn.query_first(format!("select nextval({})", ID_SEQUENCE))?;
+    match id {
+        Some(id) => Ok(Value::Int(id)),
+        None => Err(Error {
+            msg: String::from("failed to allocate id"),
+            kind: ErrorKind::Internal,
+        }),
+    }
+}
+
+thread_local! {
+    // Databases whose objects shared by all the tables have been created
+    static PREPARED_DATABASES: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+}
+
+// Objects shared by all the tables are created by the first write to the
+// database, so read-only queries never run DDL. DDL commits ongoing
+// transaction in TiDB, so it's done before any transaction begins.
+fn prepare_schema(context: &Context, conn: &mut PooledConn) -> Result<(), Error> {
+    let TiDBService {
+        ref host,
+        ref port,
+        ref database,
+        ..
+    } = context.tidb_service;
+    let key = format!("{}:{}/{}", host, port, database);
+    if PREPARED_DATABASES.with(|v| v.borrow().contains(&key)) {
+        return Ok(());
+    }
+    conn.query_drop(format!("create sequence if not exists {}", ID_SEQUENCE))?;
+    conn.query_drop(format!(
+        "create table if not exists {} (name varchar(255) primary key)",
+        LOCK_TABLE
+    ))?;
+    PREPARED_DATABASES.with(|v| v.borrow_mut().insert(key));
+    Ok(())
+}
+
+enum Connection<'a> {
+    Owned(PooledConn),
+    Shared(RefMut<'a, Option<PooledConn>>),
+}
+
+impl<'a> Deref for Connection<'a> {
+    type Target = PooledConn;
+
+    fn deref(&self) -> &PooledConn {
+        match self {
+            Connection::Owned(conn) => conn,
+            Connection::Shared(conn) => conn.as_ref().unwrap(),
+        }
+    }
+}
+
+impl<'a> DerefMut for Connection<'a> {
+    fn deref_mut(&mut self) -> &mut PooledConn {
+        match self {
+            Connection::Owned(conn) => conn,
+            Connection::Shared(conn) => conn.as_mut().unwrap(),
+        }
+    }
+}
+
+// Use connection of session if there is an ongoing transaction
+fn prepare_connection<'a>(
+    context: &Context,
+    session: &'a Option<Session>,
+) -> Result<Connection<'a>, Error> {
+    match session {
+        Some(session) if session.borrow().is_some() => Ok(Connection::Shared(session.borrow_mut())),
+        _ => Ok(Connection::Owned(prepare_tidb_connection(context)?)),
+    }
+}
+
+fn prepare_tidb_connection(context: &Context) -> Result<PooledConn, Error> {
+    let TiDBService {
+        ref host,
+        ref port,
+        ref username,
+        ref password,
+        ref database,
+        ..
+    } = context.tidb_service;
+
+    let url = format!(
+        "mysql://{}:{}@{}:{}/{}",
+        username, password, host, port, database,
+    );
+
+    let pool = Pool::new(url)?;
+    Ok(pool.get_conn()?)
+}
+
+// Scan table with filter on the scanned variable. Filter is translated into
+// SQL conditions to reduce rows to fetch, and it's still checked here in case
+// TiDB evaluates them differently.
+fn scan_table_with_filter(
+    context: &Context,
+    table_name: &String,
+    variable_name: &String,
+    conditions: &Vec<String>,
+    filter: &Vec<ScalarExpr>,
+    params: &Params,
+    conn: &mut Connection,
+) -> Result<Vec<ExecutionContext>, Error> {
+    let mut conditions = conditions.to_owned();
+    if !filter.is_empty() {
+        let columns = table_columns(context, table_name, conn)?;
+        for filter in filter.iter() {
+            if let Some(cond) = condition(filter, variable_name, &columns, params) {
+                conditions.push(cond);
+            }
+        }
+    }
+
+    let mut res = Vec::new();
+    for mut ctx in scan_table(table_name, variable_name, &conditions, conn)?.into_iter() {
+        ctx.params = params.clone();
+        if check_filter(filter, &ctx)? {
+            res.push(ctx);
+        }
+    }
+    Ok(res)
+}
+
+fn scan_table(
+    table_name: &String,
+    variable_name: &String,
+    conditions: &Vec<String>,
+    conn: &mut Connection,
+) -> Result<Vec<ExecutionContext>, Error> {
+    let mut sql = format!("select * from {}", table_name);
+    if !conditions.is_empty() {
+        sql.push_str(" where ");
+        sql.push_str(&conditions.join(" and "));
+    }
+    // Lock scanned rows until the transaction ends
+    if let Connection::Shared(_) = conn {
+        sql.push_str(" for update");
+    }
+    let mut result = conn.query_iter(sql)?;
+
+    let mut res = Vec::new();
+
+    while let Some(result_set) = result.next_set() {
+        let result_set = result_set?;
+
+        let columns: Vec<Column> = result_set.columns().as_ref().to_vec();
+        let column_names: Vec<String> = columns
+            .iter()
+            .map(|v| String::from_utf8(Vec::from(v.name_ref())).unwrap())
+            .collect();
+
+        for row in result_set {
+            let mut ctx = ExecutionContext::new();
+            let mut obj = Object::new_entity();
+            let mut row = row?;
+            for i in 0..row.len() {
+                // println!("Row: {:#?}", row);
+                let v: mysql::Value = row.take(i).unwrap();
+                obj.set(column_names.get(i).unwrap(), &column_value(&columns[i], v));
+            }
+            ctx.set(variable_name, Value::Object(obj));
+            res.push(ctx);
+        }
+    }
+
+    Ok(res)
+}
+
+// Booleans are stored in `boolean` columns, which are `tinyint(1)` in TiDB,
+// and nested values are stored in `json` columns
+fn column_value(column: &Column, value: mysql::Value) -> Value {
+    if let mysql::Value::Bytes(bytes) = &value {
+        if column.column_type() == ColumnType::MYSQL_TYPE_JSON {
+            if let Ok(v) = serde_json::from_slice::<serde_json::Value>(bytes) {
+                return Value::from(v);
+            }
+        }
+    }
+    match Value::from(value) {
+        Value::Int(v)
+            if column.column_type() == ColumnType::MYSQL_TYPE_TINY
+                && column.column_length() == 1 =>
+        {
+            Value::Boolean(v != 0)
+        }
+        v @ _ => v,
+    }
+}
+
+// Execute query and collect values of the column, only for queries not
+// accessing TiDB
+#[cfg(test)]
+pub(crate) fn execute_column(query: &str, column: &str) -> Vec<Value> {
+    let ast = crate::parser::Parser::new().parse(query).unwrap();
+    let mut exec = crate::core::Planner {}.plan(&ast).unwrap();
+    exec.open().unwrap();
+    let mut result = Vec::new();
+    while let Some(ctx) = exec.next().unwrap() {
+        result.push(ctx.get(column).unwrap().to_owned());
+    }
+    exec.close().unwrap();
+    result
+}
+
+#[test]
+fn test_parameters() {
+    use crate::optimizer::{default_implementation, Builder};
+
+    let query =
+        "UNWIND $xs AS x WITH x * $k AS y, count(*) AS c RETURN y + c AS v ORDER BY v LIMIT $n";
+    let ast = crate::parser::Parser::new().parse(query).unwrap();
+    let plan = Builder::new().build(&ast).unwrap();
+    // The same plan is executed with different parameters
+    let execute = |params: Vec<(&str, Value)>| -> Result<Vec<String>, Error> {
+        let params = params.into_iter().map(|(k, v)| (k.to_owned(), v)).collect();
+        let mut exec = default_implementation(&plan, &Rc::new(params))?;
+        exec.open()?;
+        let mut result = Vec::new();
+        while let Some(ctx) = exec.next()? {
+            result.push(ctx.get("v").unwrap().group_key());
+        }
+        exec.close()?;
+        Ok(result)
+    };
+    let list = |v: &[i64]| {
+        let mut list = Array::new();
+        list.elements = v.iter().map(|v| Value::Int(*v)).collect();
+        Value::Array(list)
+    };
+    let result = execute(vec![
+        ("xs", list(&[1, 2, 2])),
+        ("k", Value::Int(10)),
+        ("n", Value::Int(5)),
+    ]);
+    assert_eq!(result.unwrap(), vec!["i11", "i22"]);
+    let result = execute(vec![
+        ("xs", list(&[3, 1])),
+        ("k", Value::Int(1)),
+        ("n", Value::Int(1)),
+    ]);
+    assert_eq!(result.unwrap(), vec!["i2"]);
+    // Missing parameter is an error when it's evaluated
+    let result = execute(vec![("xs", list(&[1])), ("n", Value::Int(1))]);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_union() {
+    let query = "UNWIND [1, 1, 2] AS x RETURN x UNION UNWIND [2, 3, 1] AS x RETURN x";
+    let result: Vec<String> = execute_column(query, "x")
+        .iter()
+        .map(|v| v.group_key())
+        .collect();
+    assert_eq!(result, vec!["i1", "i2", "i3"]);
+
+    let query = "UNWIND [1, 1] AS x RETURN x UNION ALL UNWIND [1] AS x RETURN x";
+    assert_eq!(execute_column(query, "x").len(), 3);
+}
+
+#[test]
+fn test_aggregate_grouped_variables() {
+    // `x` is referred outside of count(), so it's a grouping key
+    let query = "UNWIND [1, 2, 2] AS x RETURN x * 10 + count(*) AS y ORDER BY y";
+    assert_eq!(
+        execute_column(query, "y"),
+        vec![Value::Int(11), Value::Int(22)]
+    );
+    let query = "UNWIND [1, 2, 2] AS x RETURN x AS x, x + count(*) AS y ORDER BY x";
+    assert_eq!(
+        execute_column(query, "y"),
+        vec![Value::Int(2), Value::Int(4)]
+    );
+}
+
+#[test]
+fn test_in_filter() {
+    // Rows whose predicate is null are filtered out like false ones
+    let query = "UNWIND [1, 2, null, 3] AS x WITH x WHERE x IN [1, 3, null] RETURN x";
+    assert_eq!(
+        execute_column(query, "x"),
+        vec![Value::Int(1), Value::Int(3)]
+    );
+    let query = "UNWIND [1, 2] AS x WITH x WHERE NOT x IN [1, null] RETURN x";
+    assert!(execute_column(query, "x").is_empty());
+    let query = "UNWIND [1, 2] AS x WITH x WHERE NOT x IN [1] RETURN x";
+    assert_eq!(execute_column(query, "x"), vec![Value::Int(2)]);
+}
+
+#[test]
+fn test_case_buckets() {
+    let query = r#"
+    UNWIND [1, 5, null] AS x
+    RETURN CASE WHEN x < 3 THEN 'low' WHEN x >= 3 THEN 'high' ELSE 'unknown' END AS b"#;
+    let result: Vec<Value> = ["low", "high", "unknown"]
+        .iter()
+        .map(|v| Value::String(v.to_string()))
+        .collect();
+    assert_eq!(execute_column(query, "b"), result);
+}
+
+#[test]
+fn test_inline_property_filter() {
+    use crate::optimizer::{Builder, PredicatePushdown};
+    use crate::util::Walker;
+
+    let query = "MATCH (a:Person {name: 'Alice', age: $age}) RETURN a";
+    let ast = crate::parser::Parser::new().parse(query).unwrap();
+    let filter = match PredicatePushdown::new().walk(Builder::new().build(&ast).unwrap()) {
+        RelExpr::Projection(project) => match *project.child {
+            RelExpr::NodeScan(scan) => scan.filter,
+            expr @ _ => panic!("unexpected plan {:#?}", expr),
+        },
+        expr @ _ => panic!("unexpected plan {:#?}", expr),
+    };
+    let params = Rc::new(
+        vec![("age".to_owned(), Value::Int(30))]
+            .into_iter()
+            .collect(),
+    );
+
+    // Rows fetched from TiDB are still checked, missing or null property and
+    // value of other type don't match
+    let name = Value::String("Alice".to_owned());
+    let cases = vec![
+        (vec![("name", name.clone()), ("age", Value::Int(30))], true),
+        (
+            vec![("name", name.clone()), ("age", Value::Double(30.0))],
+            true,
+        ),
+        (vec![("name", name.clone()), ("age", Value::Int(31))], false),
+        (
+            vec![
+                ("name", name.clone()),
+                ("age", Value::String("30".to_owned())),
+            ],
+            false,
+        ),
+        (vec![("name", name.clone()), ("age", Value::Null)], false),
+        (vec![("name", name.clone())], false),
+        (vec![("name", Value::Null), ("age", Value::Int(30))], false),
+    ];
+    for (props, expected) in cases {
+        let mut node = Object::new_entity();
+        for (key, value) in props.iter() {
+            node.set(key, value);
+        }
+        let mut ctx = ExecutionContext::with_params(&params);
+        ctx.set("a", Value::Object(node));
+        assert_eq!(
+            check_filter(&filter, &ctx).unwrap(),
+            expected,
+            "{:?}",
+            props
+        );
+    }
+}
+
+#[cfg(test)]
+fn object(props: &[(&str, i64)]) -> Value {
+    let mut obj = Object::new();
+    for (key, value) in props.iter() {
+        obj.set(key, &Value::Int(*value));
+    }
+    Value::Object(obj)
+}
+
+#[cfg(test)]
+fn rows(name: &str, values: Vec<Value>) -> Vec<ExecutionContext> {
+    values
+        .into_iter()
+        .map(|v| {
+            let mut ctx = ExecutionContext::new();
+            ctx.set(name, v);
+            ctx
+        })
+        .collect()
+}
+
+#[test]
+fn test_undirected_expand() {
+    let params = Rc::new(HashMap::new());
+    let mut expand = TiDBNestedLoopExpand {
+        context: Context::new(),
+        session: None,
+        params: params.clone(),
+        result: VecDeque::new(),
+        start_name: "a".to_owned(),
+        end_name: "b".to_owned(),
+        rel_name: "r".to_owned(),
+        rel_types: vec![],
+        end_labels: vec![],
+        undirected: false,
+        rel_filter: vec![],
+        end_filter: vec![],
+
+        child: Box::new(SingleRowExec::new(&params)),
+    };
+    // 1 has a self loop
+    let rels = rows(
+        "r",
+        vec![
+            object(&[("id", 10), ("start", 1), ("end", 2)]),
+            object(&[("id", 11), ("start", 3), ("end", 1)]),
+            object(&[("id", 12), ("start", 1), ("end", 1)]),
+            object(&[("id", 13), ("start", 2), ("end", 3)]),
+        ],
+    );
+    let ends = rows(
+        "b",
+        vec![
+            object(&[("id", 1)]),
+            object(&[("id", 2)]),
+            object(&[("id", 3)]),
+        ],
+    );
+    let start = |end: Option<i64>| {
+        let mut ctx = ExecutionContext::new();
+        ctx.set("a", object(&[("id", 1)]));
+        if let Some(end) = end {
+            ctx.set("b", object(&[("id", end)]));
+        }
+        ctx
+    };
+    let mut null_start = ExecutionContext::new();
+    null_start.set("a", Value::Null);
+
+    // Ids of relationship and end node of every row
+    let cases = vec![
+        (false, start(None), vec![(10, 2), (12, 1)]),
+        (true, start(None), vec![(10, 2), (11, 3), (12, 1)]),
+        (true, start(Some(3)), vec![(11, 3)]),
+        (true, null_start, vec![]),
+    ];
+    for (undirected, ctx, expected) in cases {
+        expand.undirected = undirected;
+        expand.expand_row(&ctx, &rels, &ends);
+        let mut result: Vec<(i64, i64)> = expand
+            .result
+            .drain(..)
+            .map(|ctx| match (ctx.get("r"), ctx.get("b")) {
+                (Some(Value::Object(r)), Some(Value::Object(b))) => {
+                    match (r.get("id"), b.get("id")) {
+                        (Some(Value::Int(r)), Some(Value::Int(b))) => (*r, *b),
+                        v @ _ => panic!("unexpected ids {:?}", v),
+                    }
+                }
+                v @ _ => panic!("unexpected row {:?}", v),
+            })
+            .collect();
+        result.sort();
+        assert_eq!(result, expected, "{}", undirected);
+    }
+}
+
+#[test]
+fn test_var_length_expand() {
+    let params = Rc::new(HashMap::new());
+    let mut expand = TiDBVarLengthExpand {
+        context: Context::new(),
+        session: None,
+        params: params.clone(),
+        result: VecDeque::new(),
+        start_name: "a".to_owned(),
+        end_name: "b".to_owned(),
+        rel_name: "r".to_owned(),
+        rel_types: vec![],
+        end_labels: vec![],
+        min_hops: 1,
+        max_hops: None,
+        undirected: false,
+        rel_filter: vec![],
+        end_filter: vec![],
+
+        child: Box::new(SingleRowExec::new(&params)),
+    };
+    // 1 -> 2 -> 1 is a cycle, and 3 has a self loop
+    let rel_rows = || {
+        rows(
+            "r",
+            vec![
+                object(&[("id", 10), ("start", 1), ("end", 2)]),
+                object(&[("id", 11), ("start", 2), ("end", 1)]),
+                object(&[("id", 12), ("start", 2), ("end", 3)]),
+                object(&[("id", 13), ("start", 3), ("end", 3)]),
+            ],
+        )
+    };
+    // End nodes are the ones with the labels and properties of the pattern
+    let end_rows = |ids: &[i64]| rows("b", ids.iter().map(|id| object(&[("id", *id)])).collect());
+    let mut start = ExecutionContext::new();
+    start.set("a", object(&[("id", 1)]));
+
+    // Paths as ids of relationships, followed by id of the end node
+    let paths = |expand: &mut TiDBVarLengthExpand,
+                 start: &ExecutionContext,
+                 end_ids: &[i64]|
+     -> Vec<Vec<i64>> {
+        let graph = expand.index_graph(rel_rows(), end_rows(end_ids));
+        expand.expand_row(start, &graph);
+        let mut paths: Vec<Vec<i64>> = expand
+            .result
+            .drain(..)
+            .map(|ctx| {
+                let mut path = Vec::new();
+                if let Some(Value::Array(rels)) = ctx.get("r") {
+                    for rel in rels.elements.iter() {
+                        if let Value::Object(rel) = rel {
+                            path.push(rel.get("id").unwrap().to_owned());
+                        }
+                    }
+                }
+                if let Some(Value::Object(end)) = ctx.get("b") {
+                    path.push(end.get("id").unwrap().to_owned());
+                }
+                path.into_iter()
+                    .map(|v| match v {
+                        Value::Int(v) => v,
+                        v @ _ => panic!("unexpected id {:?}", v),
+                    })
+                    .collect()
+            })
+            .collect();
+        paths.sort();
+        paths
+    };
+
+    // Every relationship appears at most once in a path, while nodes can be
+    // visited again. Relationships are listed in the order of the path.
+    let cases = vec![
+        (
+            1,
+            None,
+            vec![
+                vec![10, 2],
+                vec![10, 11, 1],
+                vec![10, 12, 3],
+                vec![10, 12, 13, 3],
+            ],
+        ),
+        (
+            1,
+            Some(2),
+            vec![vec![10, 2], vec![10, 11, 1], vec![10, 12, 3]],
+        ),
+        (0, Some(1), vec![vec![1], vec![10, 2]]),
+        (0, Some(0), vec![vec![1]]),
+        (3, None, vec![vec![10, 12, 13, 3]]),
+        (5, None, vec![]),
+    ];
+    for (min_hops, max_hops, expected) in cases {
+        expand.min_hops = min_hops;
+        expand.max_hops = max_hops;
+        assert_eq!(
+            paths(&mut expand, &start, &[1, 2, 3]),
+            expected,
+            "{}..{:?}",
+            min_hops,
+            max_hops
+        );
+    }
+
+    // Paths go through nodes which are not end nodes
+    expand.min_hops = 1;
+    expand.max_hops = None;
+    assert_eq!(
+        paths(&mut expand, &start, &[1, 3]),
+        vec![vec![10, 11, 1], vec![10, 12, 3], vec![10, 12, 13, 3]]
+    );
+
+    // End node binded by previous clauses
+    let mut bound = start.clone();
+    bound.set("b", object(&[("id", 3)]));
+    assert_eq!(
+        paths(&mut expand, &bound, &[1, 2, 3]),
+        vec![vec![10, 12, 3], vec![10, 12, 13, 3]]
+    );
+
+    // Null start node of OPTIONAL MATCH has no paths
+    let mut null_start = ExecutionContext::new();
+    null_start.set("a", Value::Null);
+    assert!(paths(&mut expand, &null_start, &[1, 2, 3]).is_empty());
+
+    // Relationships are traversed from either side, and self loop only once
+    expand.undirected = true;
+    let result = paths(&mut expand, &start, &[1, 2, 3]);
+    assert!(result.contains(&vec![10, 11, 1]));
+    assert!(result.contains(&vec![11, 10, 1]));
+    assert!(result.contains(&vec![10, 12, 13, 3]));
+    for path in result.iter() {
+        let mut rels = path[..path.len() - 1].to_vec();
+        rels.sort();
+        rels.dedup();
+        assert_eq!(rels.len(), path.len() - 1, "{:?}", path);
+    }
 }
@@ -0,0 +1,355 @@
+use std::{cmp::Ordering, collections::HashSet};
+
+use crate::{
+    types::{Array, Value},
+    util::ErrorKind,
+    Error,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    Collect,
+    StDev,
+    PercentileCont,
+    PercentileDisc,
+}
+
+impl AggregateFunction {
+    // Resolve aggregation function by name, function names are case insensitive
+    pub fn lookup(name: &str) -> Option<AggregateFunction> {
+        match name.to_lowercase().as_str() {
+            "count" => Some(AggregateFunction::Count),
+            "sum" => Some(AggregateFunction::Sum),
+            "avg" => Some(AggregateFunction::Avg),
+            "min" => Some(AggregateFunction::Min),
+            "max" => Some(AggregateFunction::Max),
+            "collect" => Some(AggregateFunction::Collect),
+            "stdev" => Some(AggregateFunction::StDev),
+            "percentilecont" => Some(AggregateFunction::PercentileCont),
+            "percentiledisc" => Some(AggregateFunction::PercentileDisc),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AggregateFunction::Count => "count",
+            AggregateFunction::Sum => "sum",
+            AggregateFunction::Avg => "avg",
+            AggregateFunction::Min => "min",
+            AggregateFunction::Max => "max",
+            AggregateFunction::Collect => "collect",
+            AggregateFunction::StDev => "stDev",
+            AggregateFunction::PercentileCont => "percentileCont",
+            AggregateFunction::PercentileDisc => "percentileDisc",
+        }
+    }
+
+    pub fn arity(&self) -> usize {
+        match self {
+            AggregateFunction::PercentileCont | AggregateFunction::PercentileDisc => 2,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum State {
+    Count(i64),
+    Sum(Value),
+    Avg(f64, i64),
+    Min(Value),
+    Max(Value),
+    // Values kept for functions computed over all of them
+    Values(Vec<Value>),
+}
+
+// State of an aggregation function within a group
+#[derive(Debug)]
+pub struct Accumulator {
+    func: AggregateFunction,
+    // Keys of values seen by DISTINCT aggregation
+    distinct: Option<HashSet<String>>,
+    state: State,
+    percentile: Option<f64>,
+}
+
+impl Accumulator {
+    pub fn new(func: &AggregateFunction, distinct: bool) -> Accumulator {
+        let state = match func {
+            AggregateFunction::Count => State::Count(0),
+            AggregateFunction::Sum => State::Sum(Value::Int(0)),
+            AggregateFunction::Avg => State::Avg(0.0, 0),
+            AggregateFunction::Min => State::Min(Value::Null),
+            AggregateFunction::Max => State::Max(Value::Null),
+            _ => State::Values(Vec::new()),
+        };
+        Accumulator {
+            func: func.to_owned(),
+            distinct: if distinct { Some(HashSet::new()) } else { None },
+            state: state,
+            percentile: None,
+        }
+    }
+
+    // Accumulate arguments of a row. count(*) has no argument and counts every
+    // row, otherwise null values are ignored.
+    pub fn update(&mut self, mut args: Vec<Value>) -> Result<(), Error> {
+        if args.is_empty() {
+            if let State::Count(count) = &mut self.state {
+                *count += 1;
+            }
+            return Ok(());
+        }
+        if args.len() > 1 && self.percentile.is_none() {
+            self.percentile = Some(Self::check_percentile(&args[1])?);
+        }
+        let value = args.swap_remove(0);
+        if value == Value::Null {
+            return Ok(());
+        }
+        if let Some(seen) = self.distinct.as_mut() {
+            if !seen.insert(value.group_key()) {
+                return Ok(());
+            }
+        }
+
+        match &mut self.state {
+            State::Count(count) => *count += 1,
+            State::Sum(sum) => *sum = add(sum, &self.func, &value)?,
+            State::Avg(sum, count) => {
+                *sum += to_number(&self.func, &value)?;
+                *count += 1;
+            }
+            State::Min(min) => {
+                if *min == Value::Null || value.order(min) == Ordering::Less {
+                    *min = value;
+                }
+            }
+            State::Max(max) => {
+                if *max == Value::Null || value.order(max) == Ordering::Greater {
+                    *max = value;
+                }
+            }
+            State::Values(values) => {
+                if self.func != AggregateFunction::Collect {
+                    to_number(&self.func, &value)?;
+                }
+                values.push(value);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<Value, Error> {
+        let result = match self.state {
+            State::Count(count) => Value::Int(count),
+            State::Sum(sum) => sum,
+            State::Avg(_, 0) => Value::Null,
+            State::Avg(sum, count) => Value::Double(sum / count as f64),
+            State::Min(v) | State::Max(v) => v,
+            State::Values(mut values) => match self.func {
+                AggregateFunction::Collect => Value::Array(Array { elements: values }),
+                AggregateFunction::StDev => stdev(&values),
+                _ => {
+                    values.sort_by(|l, r| l.order(r));
+                    percentile(&self.func, &values, self.percentile.unwrap_or(0.0))
+                }
+            },
+        };
+        Ok(result)
+    }
+
+    fn check_percentile(value: &Value) -> Result<f64, Error> {
+        let percentile = match value {
+            Value::Int(v) => *v as f64,
+            Value::Double(v) => *v,
+            _ => -1.0,
+        };
+        if !(0.0..=1.0).contains(&percentile) {
+            return Err(Error {
+                msg: format!(
+                    "percentile should be a number between 0 and 1, got {:?}",
+                    value
+                ),
+                kind: ErrorKind::Semantic,
+            });
+        }
+        Ok(percentile)
+    }
+}
+
+fn to_number(func: &AggregateFunction, value: &Value) -> Result<f64, Error> {
+    match value {
+        Value::Int(v) => Ok(*v as f64),
+        Value::Double(v) => Ok(*v),
+        _ => Err(Error {
+            msg: format!(
+                "function `{}` expects numbers, but got {:?}",
+                func.name(),
+                value
+            ),
+            kind: ErrorKind::Type,
+        }),
+    }
+}
+
+// Sum of integers stays integer until a double is added
+fn add(sum: &Value, func: &AggregateFunction, value: &Value) -> Result<Value, Error> {
+    match (sum, value) {
+        (Value::Int(l), Value::Int(r)) => match l.checked_add(*r) {
+            Some(v) => Ok(Value::Int(v)),
+            None => Err(Error {
+                msg: format!("integer overflow or division by zero: {} + {}", l, r),
+                kind: ErrorKind::Type,
+            }),
+        },
+        (l, r) => Ok(Value::Double(to_number(func, l)? + to_number(func, r)?)),
+    }
+}
+
+fn number(value: &Value) -> f64 {
+    match value {
+        Value::Int(v) => *v as f64,
+        Value::Double(v) => *v,
+        _ => 0.0,
+    }
+}
+
+// Sample standard deviation of numbers
+fn stdev(values: &Vec<Value>) -> Value {
+    let values: Vec<f64> = values.iter().map(number).collect();
+    if values.len() < 2 {
+        return Value::Double(0.0);
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    Value::Double(variance.sqrt())
+}
+
+// Percentile of sorted numbers, percentileCont interpolates linearly between
+// adjacent values while percentileDisc takes the nearest value
+fn percentile(func: &AggregateFunction, values: &Vec<Value>, percentile: f64) -> Value {
+    if values.is_empty() {
+        return Value::Null;
+    }
+    match func {
+        AggregateFunction::PercentileCont => {
+            let position = percentile * (values.len() - 1) as f64;
+            let lower = position.floor() as usize;
+            let upper = position.ceil() as usize;
+            let fraction = position - lower as f64;
+            let (lower, upper) = (number(&values[lower]), number(&values[upper]));
+            Value::Double(lower + (upper - lower) * fraction)
+        }
+        _ => {
+            let index = ((percentile * values.len() as f64).ceil() as usize).max(1) - 1;
+            values[index].to_owned()
+        }
+    }
+}
+
+#[cfg(test)]
+fn aggregate(
+    func: AggregateFunction,
+    distinct: bool,
+    args: Vec<Vec<Value>>,
+) -> Result<Value, Error> {
+    let mut acc = Accumulator::new(&func, distinct);
+    for arg in args.into_iter() {
+        acc.update(arg)?;
+    }
+    acc.finish()
+}
+
+#[test]
+fn test_aggregate() {
+    let rows = |values: &[Value]| values.iter().map(|v| vec![v.to_owned()]).collect();
+    let values = [
+        Value::Int(1),
+        Value::Int(2),
+        Value::Null,
+        Value::Int(2),
+        Value::Int(4),
+    ];
+
+    let cases = vec![
+        (AggregateFunction::Count, false, Value::Int(4)),
+        (AggregateFunction::Count, true, Value::Int(3)),
+        (AggregateFunction::Sum, false, Value::Int(9)),
+        (AggregateFunction::Sum, true, Value::Int(7)),
+        (AggregateFunction::Avg, false, Value::Double(2.25)),
+        (AggregateFunction::Min, false, Value::Int(1)),
+        (AggregateFunction::Max, false, Value::Int(4)),
+        (
+            AggregateFunction::Collect,
+            true,
+            Value::Array(Array {
+                elements: vec![Value::Int(1), Value::Int(2), Value::Int(4)],
+            }),
+        ),
+    ];
+    for (func, distinct, expected) in cases {
+        let result = aggregate(func.to_owned(), distinct, rows(&values)).unwrap();
+        assert_eq!(result, expected, "{:?} distinct: {}", func, distinct);
+    }
+
+    // count(*) counts rows with null
+    let result = aggregate(AggregateFunction::Count, false, vec![vec![]; 3]).unwrap();
+    assert_eq!(result, Value::Int(3));
+
+    // Aggregation over no value
+    let result = aggregate(AggregateFunction::Sum, false, vec![]).unwrap();
+    assert_eq!(result, Value::Int(0));
+    let result = aggregate(AggregateFunction::Avg, false, vec![]).unwrap();
+    assert_eq!(result, Value::Null);
+
+    // Sum of integers and doubles is a double
+    let result = aggregate(
+        AggregateFunction::Sum,
+        false,
+        rows(&[Value::Int(1), Value::Double(0.5)]),
+    )
+    .unwrap();
+    assert_eq!(result, Value::Double(1.5));
+}
+
+#[test]
+fn test_aggregate_error() {
+    let result = aggregate(
+        AggregateFunction::Sum,
+        false,
+        vec![vec![Value::Int(i64::MAX)], vec![Value::Int(1)]],
+    );
+    assert!(result.is_err());
+    let result = aggregate(
+        AggregateFunction::Avg,
+        false,
+        vec![vec![Value::String("a".to_owned())]],
+    );
+    assert!(result.is_err());
+    let result = aggregate(
+        AggregateFunction::PercentileCont,
+        false,
+        vec![vec![Value::Int(1), Value::Double(1.5)]],
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_percentile() {
+    let rows: Vec<Vec<Value>> = [4, 1, 3, 2]
+        .iter()
+        .map(|v| vec![Value::Int(*v), Value::Double(0.5)])
+        .collect();
+    let result = aggregate(AggregateFunction::PercentileCont, false, rows.clone()).unwrap();
+    assert_eq!(result, Value::Double(2.5));
+    let result = aggregate(AggregateFunction::PercentileDisc, false, rows).unwrap();
+    assert_eq!(result, Value::Int(2));
+}
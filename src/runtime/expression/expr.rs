@@ -1,29 +1,399 @@
-use crate::Error;
+use std::{cell::RefCell, cmp::Ordering, collections::HashMap};
+
+use regex::Regex;
+
 use crate::{optimizer::ScalarExpr, runtime::executor::ExecutionContext, types::Value};
+use crate::{
+    types::{Array, Object},
+    util::ErrorKind,
+    Error,
+};
 
 pub fn eval(expr: &ScalarExpr, ctx: &ExecutionContext) -> Result<Value, Error> {
     match expr {
         ScalarExpr::PropertyLookup(child, name) => eval_property_lookup(child, name, ctx),
         ScalarExpr::Variable(name) => eval_variable(name, ctx),
+        ScalarExpr::Parameter(name) => eval_parameter(name, ctx),
+        ScalarExpr::FunctionCall(func, args) => {
+            let mut values = Vec::new();
+            for arg in args.iter() {
+                values.push(eval(arg, ctx)?);
+            }
+            func.call(values)
+        }
+        ScalarExpr::Case(operand, alternatives, default) => {
+            eval_case(operand, alternatives, default, ctx)
+        }
         ScalarExpr::Equal(lhs, rhs) => eval_equal(lhs, rhs, ctx),
+        ScalarExpr::NotEqual(lhs, rhs) => eval_not_equal(lhs, rhs, ctx),
+        ScalarExpr::Less(lhs, rhs) => eval_compare(lhs, rhs, ctx, |v| v == Ordering::Less),
+        ScalarExpr::LessEqual(lhs, rhs) => eval_compare(lhs, rhs, ctx, |v| v != Ordering::Greater),
+        ScalarExpr::Greater(lhs, rhs) => eval_compare(lhs, rhs, ctx, |v| v == Ordering::Greater),
+        ScalarExpr::GreaterEqual(lhs, rhs) => eval_compare(lhs, rhs, ctx, |v| v != Ordering::Less),
+        ScalarExpr::LogicAnd(lhs, rhs) => eval_and(lhs, rhs, ctx),
+        ScalarExpr::LogicOr(lhs, rhs) => eval_or(lhs, rhs, ctx),
+        ScalarExpr::LogicXor(lhs, rhs) => eval_xor(lhs, rhs, ctx),
+        ScalarExpr::LogicNot(child) => eval_not(child, ctx),
+        ScalarExpr::IsNull(child) => Ok(Value::Boolean(eval(child, ctx)? == Value::Null)),
+        ScalarExpr::IsNotNull(child) => Ok(Value::Boolean(eval(child, ctx)? != Value::Null)),
+        ScalarExpr::StartsWith(lhs, rhs) => {
+            eval_string_predicate(lhs, rhs, ctx, |l, r| Ok(l.starts_with(r)))
+        }
+        ScalarExpr::EndsWith(lhs, rhs) => {
+            eval_string_predicate(lhs, rhs, ctx, |l, r| Ok(l.ends_with(r)))
+        }
+        ScalarExpr::Contains(lhs, rhs) => {
+            eval_string_predicate(lhs, rhs, ctx, |l, r| Ok(l.contains(r)))
+        }
+        ScalarExpr::RegexMatch(lhs, rhs) => eval_string_predicate(lhs, rhs, ctx, regex_match),
+        ScalarExpr::In(lhs, rhs) => eval_in(lhs, rhs, ctx),
+        ScalarExpr::Add(lhs, rhs) => eval_add(lhs, rhs, ctx),
+        ScalarExpr::Sub(lhs, rhs) => {
+            eval_arithmetic(lhs, rhs, ctx, "-", i64::checked_sub, |l, r| l - r)
+        }
+        ScalarExpr::Mult(lhs, rhs) => {
+            eval_arithmetic(lhs, rhs, ctx, "*", i64::checked_mul, |l, r| l * r)
+        }
+        ScalarExpr::Div(lhs, rhs) => {
+            eval_arithmetic(lhs, rhs, ctx, "/", i64::checked_div, |l, r| l / r)
+        }
+        ScalarExpr::Mod(lhs, rhs) => {
+            eval_arithmetic(lhs, rhs, ctx, "%", i64::checked_rem, |l, r| l % r)
+        }
+        ScalarExpr::Power(lhs, rhs) => eval_power(lhs, rhs, ctx),
+        ScalarExpr::Negate(child) => eval_negate(child, ctx),
+        ScalarExpr::IntegerLiteral(v) => Ok(Value::Int(v.to_owned())),
         ScalarExpr::NumberLiteral(v) => eval_number_literal(v.to_owned(), ctx),
         ScalarExpr::StringLiteral(v) => eval_string_literal(v.to_owned(), ctx),
-        _ => unimplemented!(),
+        ScalarExpr::BooleanLiteral(v) => Ok(Value::Boolean(v.to_owned())),
+        ScalarExpr::NullLiteral => Ok(Value::Null),
+        ScalarExpr::ListLiteral(elements) => eval_list_literal(elements, ctx),
+        ScalarExpr::MapLiteral(entries) => eval_map_literal(entries, ctx),
+        ScalarExpr::MapProjection(child, all, entries) => {
+            eval_map_projection(child, *all, entries, ctx)
+        }
+        ScalarExpr::Subscript(child, index) => eval_subscript(child, index, ctx),
+        ScalarExpr::Slice(child, from, to) => eval_slice(child, from, to, ctx),
+    }
+}
+
+fn eval_case(
+    operand: &Option<Box<ScalarExpr>>,
+    alternatives: &Vec<(ScalarExpr, ScalarExpr)>,
+    default: &Option<Box<ScalarExpr>>,
+    ctx: &ExecutionContext,
+) -> Result<Value, Error> {
+    let operand = match operand {
+        Some(operand) => Some(eval(operand, ctx)?),
+        None => None,
+    };
+    for (when, then) in alternatives.iter() {
+        let when = eval(when, ctx)?;
+        let matched = match operand.as_ref() {
+            Some(operand) => equal_value(operand, &when),
+            None => when,
+        };
+        if matched == Value::Boolean(true) {
+            return eval(then, ctx);
+        }
+    }
+    match default {
+        Some(default) => eval(default, ctx),
+        None => Ok(Value::Null),
     }
 }
 
 fn eval_equal(lhs: &ScalarExpr, rhs: &ScalarExpr, ctx: &ExecutionContext) -> Result<Value, Error> {
-    let res = (eval(lhs, ctx)?, eval(rhs, ctx)?);
-    match res {
-        (Value::Int(l), Value::Int(r)) => Ok(Value::Boolean(l == r)),
-        (Value::Double(l), Value::Double(r)) => Ok(Value::Boolean(l == r)),
-        (Value::String(l), Value::String(r)) => Ok(Value::Boolean(l == r)),
-        (Value::Int(l), Value::Double(r)) => Ok(Value::Boolean(l as f64 == r)),
-        (Value::Double(l), Value::Int(r)) => Ok(Value::Boolean(l == r as f64)),
-        _ => Ok(Value::Boolean(false)),
+    Ok(equal_value(&eval(lhs, ctx)?, &eval(rhs, ctx)?))
+}
+
+fn eval_not_equal(
+    lhs: &ScalarExpr,
+    rhs: &ScalarExpr,
+    ctx: &ExecutionContext,
+) -> Result<Value, Error> {
+    match equal_value(&eval(lhs, ctx)?, &eval(rhs, ctx)?) {
+        Value::Boolean(v) => Ok(Value::Boolean(!v)),
+        v @ _ => Ok(v),
+    }
+}
+
+// Equality of values, which is null if it depends on null
+fn equal_value(lhs: &Value, rhs: &Value) -> Value {
+    // Equality of lists and maps are combined like AND
+    fn equal_all<'a>(pairs: impl Iterator<Item = (&'a Value, &'a Value)>) -> Value {
+        let mut result = Value::Boolean(true);
+        for (l, r) in pairs {
+            match equal_value(l, r) {
+                Value::Boolean(false) => return Value::Boolean(false),
+                Value::Null => result = Value::Null,
+                _ => {}
+            }
+        }
+        result
+    }
+
+    match (lhs, rhs) {
+        (Value::Null, _) | (_, Value::Null) => Value::Null,
+        (Value::Int(l), Value::Int(r)) => Value::Boolean(l == r),
+        (Value::Double(l), Value::Double(r)) => Value::Boolean(l == r),
+        (Value::Int(l), Value::Double(r)) => Value::Boolean(*l as f64 == *r),
+        (Value::Double(l), Value::Int(r)) => Value::Boolean(*l == *r as f64),
+        (Value::String(l), Value::String(r)) => Value::Boolean(l == r),
+        (Value::Boolean(l), Value::Boolean(r)) => Value::Boolean(l == r),
+        (Value::Array(l), Value::Array(r)) => {
+            if l.elements.len() != r.elements.len() {
+                return Value::Boolean(false);
+            }
+            equal_all(l.elements.iter().zip(r.elements.iter()))
+        }
+        (Value::Object(l), Value::Object(r)) => {
+            if l.props.len() != r.props.len() {
+                return Value::Boolean(false);
+            }
+            let mut pairs = Vec::new();
+            for (key, l) in l.props.iter() {
+                match r.get(key) {
+                    Some(r) => pairs.push((l, r)),
+                    None => return Value::Boolean(false),
+                }
+            }
+            equal_all(pairs.into_iter())
+        }
+        _ => Value::Boolean(false),
+    }
+}
+
+// Values of different types are incomparable, and the result is null
+fn eval_compare(
+    lhs: &ScalarExpr,
+    rhs: &ScalarExpr,
+    ctx: &ExecutionContext,
+    f: fn(Ordering) -> bool,
+) -> Result<Value, Error> {
+    let ord = match (eval(lhs, ctx)?, eval(rhs, ctx)?) {
+        (l @ Value::Int(_), r @ Value::Int(_))
+        | (l @ Value::Int(_), r @ Value::Double(_))
+        | (l @ Value::Double(_), r @ Value::Int(_))
+        | (l @ Value::Double(_), r @ Value::Double(_))
+        | (l @ Value::String(_), r @ Value::String(_))
+        | (l @ Value::Boolean(_), r @ Value::Boolean(_)) => l.order(&r),
+        _ => return Ok(Value::Null),
+    };
+    Ok(Value::Boolean(f(ord)))
+}
+
+// Null if no element equals to the value, but some comparison is null
+fn eval_in(lhs: &ScalarExpr, rhs: &ScalarExpr, ctx: &ExecutionContext) -> Result<Value, Error> {
+    let value = eval(lhs, ctx)?;
+    let list = match eval(rhs, ctx)? {
+        Value::Array(list) => list,
+        Value::Null => return Ok(Value::Null),
+        v @ _ => {
+            return Err(Error {
+                msg: format!("expect list on the right of IN, but got {:?}", v),
+                kind: ErrorKind::Type,
+            })
+        }
+    };
+
+    let mut result = Value::Boolean(false);
+    for element in list.elements.iter() {
+        match equal_value(&value, element) {
+            Value::Boolean(true) => return Ok(Value::Boolean(true)),
+            Value::Null => result = Value::Null,
+            _ => {}
+        }
+    }
+    Ok(result)
+}
+
+// `+` concatenates strings and lists, and adds numbers otherwise
+fn eval_add(lhs: &ScalarExpr, rhs: &ScalarExpr, ctx: &ExecutionContext) -> Result<Value, Error> {
+    match (eval(lhs, ctx)?, eval(rhs, ctx)?) {
+        (Value::String(l), Value::String(r)) => Ok(Value::String(l + &r)),
+        (Value::Array(mut l), Value::Array(r)) => {
+            l.elements.extend(r.elements);
+            Ok(Value::Array(l))
+        }
+        (Value::Array(mut l), r @ _) => {
+            l.elements.push(r);
+            Ok(Value::Array(l))
+        }
+        (l @ _, Value::Array(mut r)) => {
+            r.elements.insert(0, l);
+            Ok(Value::Array(r))
+        }
+        (l, r) => arithmetic(l, r, "+", i64::checked_add, |l, r| l + r),
+    }
+}
+
+fn eval_arithmetic(
+    lhs: &ScalarExpr,
+    rhs: &ScalarExpr,
+    ctx: &ExecutionContext,
+    op: &str,
+    int_op: fn(i64, i64) -> Option<i64>,
+    double_op: fn(f64, f64) -> f64,
+) -> Result<Value, Error> {
+    arithmetic(eval(lhs, ctx)?, eval(rhs, ctx)?, op, int_op, double_op)
+}
+
+// Arithmetic of numbers is null if any side is null. Integers stay integers
+// unless a double is involved.
+fn arithmetic(
+    lhs: Value,
+    rhs: Value,
+    op: &str,
+    int_op: fn(i64, i64) -> Option<i64>,
+    double_op: fn(f64, f64) -> f64,
+) -> Result<Value, Error> {
+    match (&lhs, &rhs) {
+        (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+        (Value::Int(l), Value::Int(r)) => match int_op(*l, *r) {
+            Some(v) => Ok(Value::Int(v)),
+            None => Err(Error {
+                msg: format!("integer overflow or division by zero: {} {} {}", l, op, r),
+                kind: ErrorKind::Type,
+            }),
+        },
+        (Value::Int(_), Value::Double(_))
+        | (Value::Double(_), Value::Int(_))
+        | (Value::Double(_), Value::Double(_)) => {
+            Ok(Value::Double(double_op(number(&lhs), number(&rhs))))
+        }
+        _ => Err(Error {
+            msg: format!(
+                "operator `{}` expects numbers, but got {:?} and {:?}",
+                op, lhs, rhs
+            ),
+            kind: ErrorKind::Type,
+        }),
+    }
+}
+
+// Power is always a double
+fn eval_power(lhs: &ScalarExpr, rhs: &ScalarExpr, ctx: &ExecutionContext) -> Result<Value, Error> {
+    let to_double = |v| match v {
+        Value::Int(v) => Value::Double(v as f64),
+        v @ _ => v,
+    };
+    let (lhs, rhs) = (to_double(eval(lhs, ctx)?), to_double(eval(rhs, ctx)?));
+    arithmetic(lhs, rhs, "^", |_, _| None, f64::powf)
+}
+
+fn eval_negate(child: &ScalarExpr, ctx: &ExecutionContext) -> Result<Value, Error> {
+    match eval(child, ctx)? {
+        Value::Null => Ok(Value::Null),
+        Value::Int(v) => match v.checked_neg() {
+            Some(v) => Ok(Value::Int(v)),
+            None => Err(Error {
+                msg: format!("integer overflow: -{}", v),
+                kind: ErrorKind::Type,
+            }),
+        },
+        Value::Double(v) => Ok(Value::Double(-v)),
+        v @ _ => Err(Error {
+            msg: format!("operator `-` expects a number, but got {:?}", v),
+            kind: ErrorKind::Type,
+        }),
     }
 }
 
+fn number(value: &Value) -> f64 {
+    match value {
+        Value::Int(v) => *v as f64,
+        Value::Double(v) => *v,
+        _ => 0.0,
+    }
+}
+
+// String predicates are null unless both sides are strings
+fn eval_string_predicate(
+    lhs: &ScalarExpr,
+    rhs: &ScalarExpr,
+    ctx: &ExecutionContext,
+    f: fn(&str, &str) -> Result<bool, Error>,
+) -> Result<Value, Error> {
+    match (eval(lhs, ctx)?, eval(rhs, ctx)?) {
+        (Value::String(l), Value::String(r)) => Ok(Value::Boolean(f(&l, &r)?)),
+        _ => Ok(Value::Null),
+    }
+}
+
+// Maximum number of compiled regular expressions kept by each thread
+const REGEX_CACHE_SIZE: usize = 64;
+
+thread_local! {
+    static REGEX_CACHE: RefCell<HashMap<String, Regex>> = RefCell::new(HashMap::new());
+}
+
+fn regex_match(value: &str, pattern: &str) -> Result<bool, Error> {
+    REGEX_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if !cache.contains_key(pattern) {
+            // The whole string must be matched
+            let re = Regex::new(&format!("^(?:{})$", pattern)).map_err(|err| Error {
+                msg: format!("invalid regular expression `{}`: {}", pattern, err),
+                kind: ErrorKind::Semantic,
+            })?;
+            if cache.len() >= REGEX_CACHE_SIZE {
+                cache.clear();
+            }
+            cache.insert(pattern.to_owned(), re);
+        }
+        Ok(cache[pattern].is_match(value))
+    })
+}
+
+fn eval_boolean(expr: &ScalarExpr, ctx: &ExecutionContext) -> Result<Option<bool>, Error> {
+    match eval(expr, ctx)? {
+        Value::Boolean(v) => Ok(Some(v)),
+        Value::Null => Ok(None),
+        v @ _ => Err(Error {
+            msg: format!("expect boolean value, but got {:?}", v),
+            kind: ErrorKind::Type,
+        }),
+    }
+}
+
+fn from_boolean(v: Option<bool>) -> Value {
+    match v {
+        Some(v) => Value::Boolean(v),
+        None => Value::Null,
+    }
+}
+
+fn eval_and(lhs: &ScalarExpr, rhs: &ScalarExpr, ctx: &ExecutionContext) -> Result<Value, Error> {
+    let res = match (eval_boolean(lhs, ctx)?, eval_boolean(rhs, ctx)?) {
+        (Some(false), _) | (_, Some(false)) => Some(false),
+        (Some(true), Some(true)) => Some(true),
+        _ => None,
+    };
+    Ok(from_boolean(res))
+}
+
+fn eval_or(lhs: &ScalarExpr, rhs: &ScalarExpr, ctx: &ExecutionContext) -> Result<Value, Error> {
+    let res = match (eval_boolean(lhs, ctx)?, eval_boolean(rhs, ctx)?) {
+        (Some(true), _) | (_, Some(true)) => Some(true),
+        (Some(false), Some(false)) => Some(false),
+        _ => None,
+    };
+    Ok(from_boolean(res))
+}
+
+fn eval_xor(lhs: &ScalarExpr, rhs: &ScalarExpr, ctx: &ExecutionContext) -> Result<Value, Error> {
+    let res = match (eval_boolean(lhs, ctx)?, eval_boolean(rhs, ctx)?) {
+        (Some(l), Some(r)) => Some(l != r),
+        _ => None,
+    };
+    Ok(from_boolean(res))
+}
+
+fn eval_not(child: &ScalarExpr, ctx: &ExecutionContext) -> Result<Value, Error> {
+    Ok(from_boolean(eval_boolean(child, ctx)?.map(|v| !v)))
+}
+
 fn eval_property_lookup(
     child: &ScalarExpr,
     prop_name: &String,
@@ -39,7 +409,20 @@ fn eval_property_lookup(
 fn eval_variable(name: &String, ctx: &ExecutionContext) -> Result<Value, Error> {
     match ctx.get(name) {
         Some(v) => Ok(v.to_owned()),
-        _ => Ok(Value::Null),
+        None => Err(Error {
+            msg: format!("variable `{}` is not binded", name),
+            kind: ErrorKind::Internal,
+        }),
+    }
+}
+
+fn eval_parameter(name: &String, ctx: &ExecutionContext) -> Result<Value, Error> {
+    match ctx.params.get(name) {
+        Some(v) => Ok(v.to_owned()),
+        None => Err(Error {
+            msg: format!("parameter `${}` is not given", name),
+            kind: ErrorKind::Semantic,
+        }),
     }
 }
 
@@ -50,3 +433,286 @@ fn eval_number_literal(value: f64, _: &ExecutionContext) -> Result<Value, Error>
 fn eval_string_literal(value: String, _: &ExecutionContext) -> Result<Value, Error> {
     Ok(Value::String(value))
 }
+
+fn eval_list_literal(elements: &Vec<ScalarExpr>, ctx: &ExecutionContext) -> Result<Value, Error> {
+    let mut array = Array::new();
+    for e in elements.iter() {
+        array.elements.push(eval(e, ctx)?);
+    }
+    Ok(Value::Array(array))
+}
+
+fn eval_map_literal(
+    entries: &Vec<(String, ScalarExpr)>,
+    ctx: &ExecutionContext,
+) -> Result<Value, Error> {
+    let mut obj = Object::new();
+    for (key, value) in entries.iter() {
+        obj.set(key, &eval(value, ctx)?);
+    }
+    Ok(Value::Object(obj))
+}
+
+// Map projection of null is null. Entries override properties selected by
+// `.*`.
+fn eval_map_projection(
+    child: &ScalarExpr,
+    all: bool,
+    entries: &Vec<(String, ScalarExpr)>,
+    ctx: &ExecutionContext,
+) -> Result<Value, Error> {
+    let mut obj = match eval(child, ctx)? {
+        Value::Null => return Ok(Value::Null),
+        Value::Object(obj) if all => obj,
+        Value::Object(_) => Object::new(),
+        v @ _ => {
+            return Err(Error {
+                msg: format!("expect map in map projection, but got {:?}", v),
+                kind: ErrorKind::Type,
+            })
+        }
+    };
+    for (key, value) in entries.iter() {
+        obj.set(key, &eval(value, ctx)?);
+    }
+    Ok(Value::Object(obj))
+}
+
+fn eval_subscript(
+    child: &ScalarExpr,
+    index: &ScalarExpr,
+    ctx: &ExecutionContext,
+) -> Result<Value, Error> {
+    match (eval(child, ctx)?, eval(index, ctx)?) {
+        (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+        (Value::Array(array), index) => {
+            let len = array.elements.len() as i64;
+            let mut index = eval_index(&index)?;
+            if index < 0 {
+                index += len;
+            }
+            if index < 0 || index >= len {
+                Ok(Value::Null)
+            } else {
+                Ok(array.elements[index as usize].to_owned())
+            }
+        }
+        (Value::Object(obj), Value::String(key)) => {
+            Ok(obj.get(&key).unwrap_or(&Value::Null).to_owned())
+        }
+        (child, index) => Err(Error {
+            msg: format!("cannot access {:?} with index {:?}", child, index),
+            kind: ErrorKind::Type,
+        }),
+    }
+}
+
+fn eval_slice(
+    child: &ScalarExpr,
+    from: &Option<Box<ScalarExpr>>,
+    to: &Option<Box<ScalarExpr>>,
+    ctx: &ExecutionContext,
+) -> Result<Value, Error> {
+    let array = match eval(child, ctx)? {
+        Value::Array(array) => array,
+        Value::Null => return Ok(Value::Null),
+        v @ _ => {
+            return Err(Error {
+                msg: format!("cannot slice {:?}", v),
+                kind: ErrorKind::Type,
+            })
+        }
+    };
+
+    // Negative bounds are counted from the end, and bounds out of range are
+    // clamped to the list
+    let len = array.elements.len() as i64;
+    let mut bounds = [0, len];
+    for (i, bound) in [from, to].iter().enumerate() {
+        if let Some(bound) = bound {
+            let v = eval(bound, ctx)?;
+            if v == Value::Null {
+                return Ok(Value::Null);
+            }
+            let v = eval_index(&v)?;
+            bounds[i] = if v < 0 { v + len } else { v }.max(0).min(len);
+        }
+    }
+
+    let mut result = Array::new();
+    if bounds[0] < bounds[1] {
+        result.elements = array.elements[bounds[0] as usize..bounds[1] as usize].to_vec();
+    }
+    Ok(Value::Array(result))
+}
+
+fn eval_index(index: &Value) -> Result<i64, Error> {
+    match index {
+        Value::Int(v) => Ok(*v),
+        Value::Double(v) if v.fract() == 0.0 => Ok(*v as i64),
+        v @ _ => Err(Error {
+            msg: format!("list index must be an integer, but got {:?}", v),
+            kind: ErrorKind::Type,
+        }),
+    }
+}
+
+#[cfg(test)]
+fn eval_query(expr: &str) -> Value {
+    let query = format!("RETURN {} AS v", expr);
+    crate::runtime::executor::execute_column(&query, "v").remove(0)
+}
+
+#[test]
+fn test_eval_three_valued_logic() {
+    let cases = vec![
+        ("true AND null", Value::Null),
+        ("false AND null", Value::Boolean(false)),
+        ("null AND null", Value::Null),
+        ("true OR null", Value::Boolean(true)),
+        ("false OR null", Value::Null),
+        ("true XOR null", Value::Null),
+        ("true XOR false", Value::Boolean(true)),
+        ("NOT null", Value::Null),
+        ("NOT false", Value::Boolean(true)),
+    ];
+    for (expr, expected) in cases {
+        assert_eq!(eval_query(expr), expected, "{}", expr);
+    }
+}
+
+#[test]
+fn test_regex_match() {
+    assert_eq!(regex_match("abc", "a.c").unwrap(), true);
+    assert_eq!(regex_match("xabc", "a.c").unwrap(), false);
+    assert_eq!(regex_match("abcd", "a.c").unwrap(), false);
+    assert!(regex_match("abc", "(").is_err());
+}
+
+#[test]
+fn test_eval_arithmetic() {
+    let cases = vec![
+        ("7 / 2", Value::Int(3)),
+        ("7 / 2.0", Value::Double(3.5)),
+        ("7 % 2", Value::Int(1)),
+        ("1 + 2 * 3 - 4", Value::Int(3)),
+        ("-0x10", Value::Int(-16)),
+        ("2 ^ 3", Value::Double(8.0)),
+        ("1e3 + 1", Value::Double(1001.0)),
+        ("1 + null", Value::Null),
+    ];
+    for (expr, expected) in cases {
+        assert_eq!(eval_query(expr), expected, "{}", expr);
+    }
+}
+
+#[cfg(test)]
+fn strings(values: &[&str]) -> Value {
+    let mut array = Array::new();
+    array.elements = values
+        .iter()
+        .map(|v| Value::String(v.to_string()))
+        .collect();
+    Value::Array(array)
+}
+
+#[test]
+fn test_eval_subscript_and_slice() {
+    let cases = vec![
+        ("['a', 'b', 'c'][0]", Value::String("a".to_string())),
+        ("['a', 'b', 'c'][-1]", Value::String("c".to_string())),
+        ("['a', 'b', 'c'][3]", Value::Null),
+        ("['a', 'b', 'c'][-4]", Value::Null),
+        ("['a', 'b', 'c'][null]", Value::Null),
+        ("null[0]", Value::Null),
+        ("{k: 'v'}['k']", Value::String("v".to_string())),
+        ("{k: 'v'}['x']", Value::Null),
+        ("{k: 'v'}[null]", Value::Null),
+        ("[['a', 'b'], ['c']][0][1]", Value::String("b".to_string())),
+        ("['a', 'b', 'c'][1..]", strings(&["b", "c"])),
+        ("['a', 'b', 'c'][..-1]", strings(&["a", "b"])),
+        ("['a', 'b', 'c'][-2..10]", strings(&["b", "c"])),
+        ("['a', 'b', 'c'][..]", strings(&["a", "b", "c"])),
+        ("['a', 'b', 'c'][2..1]", strings(&[])),
+        ("['a', 'b', 'c'][null..]", Value::Null),
+        ("['a', 'b', 'c'][..null]", Value::Null),
+    ];
+    for (expr, expected) in cases {
+        assert_eq!(eval_query(expr), expected, "{}", expr);
+    }
+}
+
+#[test]
+fn test_eval_in() {
+    let cases = vec![
+        ("'a' IN ['a', null]", Value::Boolean(true)),
+        ("'b' IN ['a', null]", Value::Null),
+        ("'b' IN ['a', 'c']", Value::Boolean(false)),
+        ("1 IN [1.0]", Value::Boolean(true)),
+        ("null IN ['a']", Value::Null),
+        ("null IN []", Value::Boolean(false)),
+        ("'a' IN null", Value::Null),
+        ("NOT 'b' IN ['a', null]", Value::Null),
+    ];
+    for (expr, expected) in cases {
+        assert_eq!(eval_query(expr), expected, "{}", expr);
+    }
+
+    // List given as parameter
+    let expr = ScalarExpr::In(
+        Box::new(ScalarExpr::StringLiteral("b".to_owned())),
+        Box::new(ScalarExpr::Parameter("names".to_owned())),
+    );
+    let eval_with = |names: Value| {
+        let params = vec![("names".to_owned(), names)].into_iter().collect();
+        eval(
+            &expr,
+            &ExecutionContext::with_params(&std::rc::Rc::new(params)),
+        )
+        .unwrap()
+    };
+    assert_eq!(eval_with(strings(&["a", "b"])), Value::Boolean(true));
+    assert_eq!(eval_with(strings(&[])), Value::Boolean(false));
+    assert_eq!(eval_with(Value::Null), Value::Null);
+}
+
+#[test]
+fn test_eval_case() {
+    let cases = vec![
+        (
+            "CASE 'b' WHEN 'a' THEN 'x' WHEN 'b' THEN 'y' END",
+            Value::String("y".to_string()),
+        ),
+        (
+            "CASE 'b' WHEN 'b' THEN 'x' WHEN 'b' THEN 'y' END",
+            Value::String("x".to_string()),
+        ),
+        (
+            "CASE 'c' WHEN 'a' THEN 'x' ELSE 'z' END",
+            Value::String("z".to_string()),
+        ),
+        ("CASE 'c' WHEN 'a' THEN 'x' END", Value::Null),
+        (
+            "CASE WHEN null THEN 'x' WHEN true THEN 'y' END",
+            Value::String("y".to_string()),
+        ),
+        ("CASE WHEN false THEN 'x' END", Value::Null),
+        (
+            "CASE null WHEN null THEN 'x' ELSE 'y' END",
+            Value::String("y".to_string()),
+        ),
+        (
+            "CASE 1 WHEN 1.0 THEN 'x' END",
+            Value::String("x".to_string()),
+        ),
+    ];
+    for (expr, expected) in cases {
+        assert_eq!(eval_query(expr), expected, "{}", expr);
+    }
+}
+
+#[test]
+fn test_eval_unbound_variable() {
+    let expr = ScalarExpr::Variable("x".to_owned());
+    assert!(eval(&expr, &ExecutionContext::new()).is_err());
+}
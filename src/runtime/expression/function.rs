@@ -0,0 +1,200 @@
+use std::fmt::Debug;
+
+use super::scalar::*;
+use crate::{optimizer::ScalarExpr, types::Value, util::ErrorKind, Error};
+
+// Types of function arguments, which are checked before calling functions.
+// Null is accepted by arguments of any type.
+#[derive(Debug, PartialEq)]
+pub enum ArgType {
+    Any,
+    String,
+    List,
+    Entity,
+}
+
+impl ArgType {
+    fn accept(&self, value: &Value) -> bool {
+        match (self, value) {
+            (ArgType::Any, _) | (_, Value::Null) => true,
+            (ArgType::String, Value::String(_)) => true,
+            (ArgType::List, Value::Array(_)) => true,
+            (ArgType::Entity, Value::Object(obj)) => obj.entity,
+            _ => false,
+        }
+    }
+
+    // Literal arguments can be checked when building plan, while other
+    // arguments are only known when evaluated
+    fn accept_expr(&self, expr: &ScalarExpr) -> bool {
+        match (self, expr) {
+            (ArgType::Any, _) | (_, ScalarExpr::NullLiteral) => true,
+            (ArgType::String, ScalarExpr::StringLiteral(_)) => true,
+            (ArgType::List, ScalarExpr::ListLiteral(_)) => true,
+            // Maps are never entities, even if they are projected from entities
+            (_, ScalarExpr::IntegerLiteral(_))
+            | (_, ScalarExpr::NumberLiteral(_))
+            | (_, ScalarExpr::StringLiteral(_))
+            | (_, ScalarExpr::BooleanLiteral(_))
+            | (_, ScalarExpr::ListLiteral(_))
+            | (_, ScalarExpr::MapLiteral(_))
+            | (_, ScalarExpr::MapProjection(..)) => false,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ScalarFunction {
+    // Function names are case insensitive, so they are registered in lower case
+    pub name: &'static str,
+    // Accepted types of every argument
+    pub args: &'static [&'static [ArgType]],
+    // Whether the last argument can be repeated
+    pub variadic: bool,
+    pub eval: fn(Vec<Value>) -> Result<Value, Error>,
+}
+
+impl ScalarFunction {
+    fn check_arity(&self, arity: usize) -> bool {
+        if self.variadic {
+            arity >= self.args.len()
+        } else {
+            arity == self.args.len()
+        }
+    }
+
+    // Accepted types of the i-th argument, where repeated arguments of
+    // variadic function share types of the last one
+    fn arg_types(&self, i: usize) -> Result<&'static [ArgType], Error> {
+        let types = match self.args.get(i) {
+            Some(types) => Some(types),
+            None if self.variadic => self.args.last(),
+            None => None,
+        };
+        match types {
+            Some(types) => Ok(types),
+            None => Err(Error {
+                msg: format!(
+                    "wrong number of arguments for function `{}`: {}",
+                    self.name,
+                    i + 1
+                ),
+                kind: ErrorKind::Semantic,
+            }),
+        }
+    }
+
+    fn type_error(&self, i: usize, types: &[ArgType], arg: impl Debug) -> Error {
+        Error {
+            msg: format!(
+                "argument {} of function `{}` expects {:?}, but got {:?}",
+                i + 1,
+                self.name,
+                types,
+                arg
+            ),
+            kind: ErrorKind::Type,
+        }
+    }
+
+    // Check arguments when building plan
+    fn check_args(&self, args: &[ScalarExpr]) -> Result<(), Error> {
+        for (i, arg) in args.iter().enumerate() {
+            let types = self.arg_types(i)?;
+            if !types.iter().any(|v| v.accept_expr(arg)) {
+                return Err(self.type_error(i, types, arg));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn call(&self, args: Vec<Value>) -> Result<Value, Error> {
+        for (i, arg) in args.iter().enumerate() {
+            let types = self.arg_types(i)?;
+            if !types.iter().any(|v| v.accept(arg)) {
+                return Err(self.type_error(i, types, arg));
+            }
+        }
+        (self.eval)(args)
+    }
+}
+
+static SCALAR_FUNCTIONS: &[ScalarFunction] = &[
+    ScalarFunction {
+        name: "coalesce",
+        args: &[&[ArgType::Any]],
+        variadic: true,
+        eval: coalesce,
+    },
+    ScalarFunction {
+        name: "id",
+        args: &[&[ArgType::Entity]],
+        variadic: false,
+        eval: id,
+    },
+    ScalarFunction {
+        name: "size",
+        args: &[&[ArgType::String, ArgType::List]],
+        variadic: false,
+        eval: size,
+    },
+    ScalarFunction {
+        name: "toupper",
+        args: &[&[ArgType::String]],
+        variadic: false,
+        eval: to_upper,
+    },
+];
+
+// Resolve function by name, and check number and types of arguments
+pub fn lookup_function(name: &str, args: &[ScalarExpr]) -> Result<&'static ScalarFunction, Error> {
+    let lower_name = name.to_lowercase();
+    match SCALAR_FUNCTIONS.iter().find(|v| v.name == lower_name) {
+        Some(func) if func.check_arity(args.len()) => {
+            func.check_args(args)?;
+            Ok(func)
+        }
+        Some(_) => Err(Error {
+            msg: format!(
+                "wrong number of arguments for function `{}`: {}",
+                name,
+                args.len()
+            ),
+            kind: ErrorKind::Semantic,
+        }),
+        None => Err(Error {
+            msg: format!("unknown function `{}`", name),
+            kind: ErrorKind::Semantic,
+        }),
+    }
+}
+
+#[test]
+fn test_call_function() {
+    let coalesce = lookup_function("COALESCE", &[ScalarExpr::NullLiteral]).unwrap();
+    let args = vec![Value::Null, Value::Int(1), Value::Int(2)];
+    assert_eq!(coalesce.call(args).unwrap(), Value::Int(1));
+
+    let to_upper = lookup_function("toUpper", &[ScalarExpr::NullLiteral]).unwrap();
+    assert!(to_upper.call(vec![Value::Int(1)]).is_err());
+
+    // Only nodes and relationships have ids, maps with `id` don't
+    let id = lookup_function("id", &[ScalarExpr::NullLiteral]).unwrap();
+    let mut map = crate::types::Object::new();
+    map.set("id", &Value::Int(1));
+    assert!(id.call(vec![Value::Object(map.to_owned())]).is_err());
+    let mut entity = crate::types::Object::new_entity();
+    entity.props = map.props;
+    assert_eq!(id.call(vec![Value::Object(entity)]).unwrap(), Value::Int(1));
+    assert_eq!(id.call(vec![Value::Null]).unwrap(), Value::Null);
+
+    // Extra arguments are rejected rather than indexing out of signature
+    let nullary = ScalarFunction {
+        name: "nullary",
+        args: &[],
+        variadic: false,
+        eval: coalesce.eval,
+    };
+    assert!(nullary.call(vec![Value::Null]).is_err());
+}
@@ -1,6 +1,9 @@
+mod aggregate;
 mod expr;
 mod function;
 
 mod scalar;
 
-pub use expr::*;
\ No newline at end of file
+pub use aggregate::{Accumulator, AggregateFunction};
+pub use expr::*;
+pub use function::{lookup_function, ScalarFunction};
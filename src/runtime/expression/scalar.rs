@@ -0,0 +1,33 @@
+use crate::{types::Value, Error};
+
+// Built-in scalar functions. Arguments have been checked against signatures
+// of functions before they are called.
+
+pub fn coalesce(args: Vec<Value>) -> Result<Value, Error> {
+    Ok(args
+        .into_iter()
+        .find(|v| v != &Value::Null)
+        .unwrap_or(Value::Null))
+}
+
+pub fn id(args: Vec<Value>) -> Result<Value, Error> {
+    match &args[0] {
+        Value::Object(obj) if obj.entity => Ok(obj.get("id").unwrap_or(&Value::Null).to_owned()),
+        _ => Ok(Value::Null),
+    }
+}
+
+pub fn size(args: Vec<Value>) -> Result<Value, Error> {
+    match &args[0] {
+        Value::String(v) => Ok(Value::Int(v.chars().count() as i64)),
+        Value::Array(v) => Ok(Value::Int(v.elements.len() as i64)),
+        _ => Ok(Value::Null),
+    }
+}
+
+pub fn to_upper(args: Vec<Value>) -> Result<Value, Error> {
+    match &args[0] {
+        Value::String(v) => Ok(Value::String(v.to_uppercase())),
+        _ => Ok(Value::Null),
+    }
+}
@@ -1,5 +1,7 @@
 mod executor;
 mod expression;
+mod sql;
 
 pub use executor::Executor;
 pub use executor::*;
+pub use expression::{lookup_function, AggregateFunction, ScalarFunction};
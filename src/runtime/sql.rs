@@ -0,0 +1,378 @@
+use std::collections::HashMap;
+
+use crate::{
+    optimizer::ScalarExpr,
+    types::{Array, Value},
+};
+
+// Translate predicate on properties of variable `name` into SQL condition.
+// Returns None if the predicate cannot be evaluated by TiDB, or it refers to
+// columns not in `columns`, which are names and types of columns. Values are
+// only compared with columns of the same type, as TiDB converts them when
+// types differ while Cypher doesn't.
+pub fn condition(
+    expr: &ScalarExpr,
+    name: &str,
+    columns: &Vec<(String, String)>,
+    params: &HashMap<String, Value>,
+) -> Option<String> {
+    match expr {
+        ScalarExpr::Equal(lhs, rhs) => {
+            let ((column, column_type), value) = match column(lhs, name, columns) {
+                Some(column) => (column, constant(rhs, params)?),
+                None => (column(rhs, name, columns)?, constant(lhs, params)?),
+            };
+            match value {
+                // Comparison with null is never true
+                Value::Null => Some("false".to_owned()),
+                v @ _ if same_type(column_type, &v) => Some(format!("{} = {}", column, literal(v))),
+                _ => None,
+            }
+        }
+        ScalarExpr::StartsWith(lhs, rhs) => like(lhs, rhs, name, columns, params, "", "%"),
+        ScalarExpr::EndsWith(lhs, rhs) => like(lhs, rhs, name, columns, params, "%", ""),
+        ScalarExpr::Contains(lhs, rhs) => like(lhs, rhs, name, columns, params, "%", "%"),
+        ScalarExpr::RegexMatch(lhs, rhs) => {
+            let column = string_column(lhs, name, columns)?;
+            // Regular expression of Cypher must match the whole string
+            let pattern = format!("^(?:{})$", string(rhs, params)?);
+            Some(format!(
+                "{} regexp {}",
+                column,
+                literal(Value::String(pattern))
+            ))
+        }
+        ScalarExpr::In(lhs, rhs) => {
+            let (column, column_type) = column(lhs, name, columns)?;
+            let list = match constant(rhs, params)? {
+                Value::Array(list) => list,
+                _ => return None,
+            };
+            // Every element must have the type of the column, except nulls,
+            // which never match in both TiDB and Cypher
+            let mut values = Vec::new();
+            for element in list.elements.into_iter() {
+                match element {
+                    Value::Null => values.push(literal(Value::Null)),
+                    v @ _ if same_type(column_type, &v) => values.push(literal(v)),
+                    _ => return None,
+                }
+            }
+            if values.is_empty() {
+                Some("false".to_owned())
+            } else {
+                Some(format!("{} in ({})", column, values.join(", ")))
+            }
+        }
+        _ => None,
+    }
+}
+
+fn like(
+    lhs: &ScalarExpr,
+    rhs: &ScalarExpr,
+    name: &str,
+    columns: &Vec<(String, String)>,
+    params: &HashMap<String, Value>,
+    prefix: &str,
+    suffix: &str,
+) -> Option<String> {
+    let column = string_column(lhs, name, columns)?;
+    let pattern = string(rhs, params)?
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    let pattern = format!("{}{}{}", prefix, pattern, suffix);
+    Some(format!(
+        "{} like {}",
+        column,
+        literal(Value::String(pattern))
+    ))
+}
+
+// Column and its type of property lookup or id of variable `name`
+fn column<'a>(
+    expr: &ScalarExpr,
+    name: &str,
+    columns: &'a Vec<(String, String)>,
+) -> Option<(String, &'a str)> {
+    let key = match expr {
+        ScalarExpr::FunctionCall(func, args) if func.name == "id" => match args.get(0) {
+            Some(ScalarExpr::Variable(v)) if v == name => "id",
+            _ => return None,
+        },
+        ScalarExpr::PropertyLookup(child, key) => match child.as_ref() {
+            ScalarExpr::Variable(v) if v == name => key.as_str(),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let (_, column_type) = columns.iter().find(|(column, _)| column == key)?;
+    Some((
+        format!("`{}`", key.replace('`', "``")),
+        column_type.as_str(),
+    ))
+}
+
+// Column of string type, other values are converted to strings by TiDB when
+// matching them with patterns
+fn string_column(expr: &ScalarExpr, name: &str, columns: &Vec<(String, String)>) -> Option<String> {
+    match column(expr, name, columns)? {
+        (column, column_type) if is_string_type(column_type) => Some(column),
+        _ => None,
+    }
+}
+
+// Whether values of the column have the same type as `value`. Booleans are
+// stored in `tinyint(1)`, which may hold other integers, so they are never
+// compared by TiDB.
+fn same_type(column_type: &str, value: &Value) -> bool {
+    match value {
+        Value::String(_) => is_string_type(column_type),
+        Value::Int(_) | Value::Double(_) => {
+            !column_type.to_lowercase().starts_with("tinyint(1)")
+                && matches!(
+                    base_type(column_type).as_str(),
+                    "tinyint" | "smallint" | "mediumint" | "int" | "bigint" | "float" | "double"
+                )
+        }
+        _ => false,
+    }
+}
+
+fn is_string_type(column_type: &str) -> bool {
+    matches!(
+        base_type(column_type).as_str(),
+        "char" | "varchar" | "tinytext" | "text" | "mediumtext" | "longtext"
+    )
+}
+
+// Type without length and attributes, e.g. `int` of `int(11) unsigned`
+fn base_type(column_type: &str) -> String {
+    column_type
+        .split(|c| c == '(' || c == ' ')
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+fn constant(expr: &ScalarExpr, params: &HashMap<String, Value>) -> Option<Value> {
+    match expr {
+        ScalarExpr::Parameter(name) => params.get(name).cloned(),
+        ScalarExpr::IntegerLiteral(v) => Some(Value::Int(v.to_owned())),
+        ScalarExpr::NumberLiteral(v) => Some(Value::Double(v.to_owned())),
+        ScalarExpr::StringLiteral(v) => Some(Value::String(v.to_owned())),
+        ScalarExpr::BooleanLiteral(v) => Some(Value::Boolean(v.to_owned())),
+        ScalarExpr::NullLiteral => Some(Value::Null),
+        ScalarExpr::Negate(child) => match constant(child, params)? {
+            Value::Int(v) => Some(Value::Int(v.checked_neg()?)),
+            Value::Double(v) => Some(Value::Double(-v)),
+            _ => None,
+        },
+        ScalarExpr::ListLiteral(elements) => {
+            let mut list = Array::new();
+            for e in elements.iter() {
+                list.elements.push(constant(e, params)?);
+            }
+            Some(Value::Array(list))
+        }
+        _ => None,
+    }
+}
+
+fn string(expr: &ScalarExpr, params: &HashMap<String, Value>) -> Option<String> {
+    match constant(expr, params)? {
+        Value::String(v) => Some(v),
+        _ => None,
+    }
+}
+
+fn literal(value: Value) -> String {
+    mysql::Value::from(value).as_sql(false)
+}
+
+#[cfg(test)]
+fn property(name: &str, key: &str) -> Box<ScalarExpr> {
+    Box::new(ScalarExpr::PropertyLookup(
+        Box::new(ScalarExpr::Variable(name.to_owned())),
+        key.to_owned(),
+    ))
+}
+
+#[cfg(test)]
+fn string_literal(value: &str) -> Box<ScalarExpr> {
+    Box::new(ScalarExpr::StringLiteral(value.to_owned()))
+}
+
+#[cfg(test)]
+fn integer_literal(value: i64) -> Box<ScalarExpr> {
+    Box::new(ScalarExpr::IntegerLiteral(value))
+}
+
+#[test]
+fn test_condition() {
+    let columns = vec![
+        ("id".to_owned(), "bigint(20)".to_owned()),
+        ("name".to_owned(), "varchar(255)".to_owned()),
+        ("age".to_owned(), "int(11)".to_owned()),
+        ("active".to_owned(), "tinyint(1)".to_owned()),
+        ("tags".to_owned(), "json".to_owned()),
+    ];
+    let cases = vec![
+        (
+            ScalarExpr::Equal(property("a", "name"), string_literal("Alice")),
+            Some("`name` = 'Alice'"),
+        ),
+        (
+            ScalarExpr::Equal(string_literal("Alice"), property("a", "name")),
+            Some("`name` = 'Alice'"),
+        ),
+        (
+            ScalarExpr::Equal(property("a", "name"), Box::new(ScalarExpr::NullLiteral)),
+            Some("false"),
+        ),
+        (
+            ScalarExpr::StartsWith(property("a", "name"), string_literal("A%_")),
+            Some("`name` like 'A\\\\%\\\\_%'"),
+        ),
+        (
+            ScalarExpr::EndsWith(property("a", "name"), string_literal("it's")),
+            Some("`name` like '%it\\'s'"),
+        ),
+        (
+            ScalarExpr::Contains(property("a", "name"), string_literal("li")),
+            Some("`name` like '%li%'"),
+        ),
+        (
+            ScalarExpr::RegexMatch(property("a", "name"), string_literal("A.*")),
+            Some("`name` regexp '^(?:A.*)$'"),
+        ),
+        (
+            ScalarExpr::In(
+                property("a", "name"),
+                Box::new(ScalarExpr::ListLiteral(vec![
+                    *string_literal("Alice"),
+                    *string_literal("Bob"),
+                ])),
+            ),
+            Some("`name` in ('Alice', 'Bob')"),
+        ),
+        (
+            ScalarExpr::In(
+                property("a", "name"),
+                Box::new(ScalarExpr::ListLiteral(vec![])),
+            ),
+            Some("false"),
+        ),
+        (
+            ScalarExpr::Equal(property("a", "age"), integer_literal(30)),
+            Some("`age` = 30"),
+        ),
+        // Values of other types are converted by TiDB, so they are compared
+        // by Cypher
+        (
+            ScalarExpr::Equal(property("a", "name"), integer_literal(1)),
+            None,
+        ),
+        (
+            ScalarExpr::Equal(property("a", "age"), string_literal("30")),
+            None,
+        ),
+        (
+            ScalarExpr::Equal(
+                property("a", "active"),
+                Box::new(ScalarExpr::BooleanLiteral(true)),
+            ),
+            None,
+        ),
+        (
+            ScalarExpr::Equal(property("a", "active"), integer_literal(1)),
+            None,
+        ),
+        (
+            ScalarExpr::Equal(
+                property("a", "tags"),
+                Box::new(ScalarExpr::ListLiteral(vec![*string_literal("a")])),
+            ),
+            None,
+        ),
+        (
+            ScalarExpr::In(
+                property("a", "age"),
+                Box::new(ScalarExpr::ListLiteral(vec![
+                    *integer_literal(30),
+                    ScalarExpr::NullLiteral,
+                ])),
+            ),
+            Some("`age` in (30, NULL)"),
+        ),
+        (
+            ScalarExpr::In(
+                property("a", "age"),
+                Box::new(ScalarExpr::ListLiteral(vec![
+                    *integer_literal(30),
+                    *string_literal("30"),
+                ])),
+            ),
+            None,
+        ),
+        (
+            ScalarExpr::In(
+                property("a", "name"),
+                Box::new(ScalarExpr::ListLiteral(vec![ScalarExpr::ListLiteral(
+                    vec![*string_literal("Alice")],
+                )])),
+            ),
+            None,
+        ),
+        // Patterns only match string columns
+        (
+            ScalarExpr::StartsWith(property("a", "age"), string_literal("3")),
+            None,
+        ),
+        (
+            ScalarExpr::Contains(property("a", "tags"), string_literal("a")),
+            None,
+        ),
+        (
+            ScalarExpr::RegexMatch(property("a", "age"), string_literal("3.*")),
+            None,
+        ),
+        // Unknown column, other variable and non-constant operand
+        (
+            ScalarExpr::Equal(property("a", "email"), string_literal("Alice")),
+            None,
+        ),
+        (
+            ScalarExpr::Equal(property("b", "name"), string_literal("Alice")),
+            None,
+        ),
+        (
+            ScalarExpr::Equal(property("a", "name"), property("a", "name")),
+            None,
+        ),
+    ];
+    for (expr, expected) in cases {
+        assert_eq!(
+            condition(&expr, "a", &columns, &HashMap::new()).as_deref(),
+            expected,
+            "{:?}",
+            expr
+        );
+    }
+
+    // Parameters are pushed down with their values given when executing
+    let mut params = HashMap::new();
+    params.insert("age".to_owned(), Value::Int(30));
+    params.insert("name".to_owned(), Value::Int(1));
+    let parameter = |name: &str| Box::new(ScalarExpr::Parameter(name.to_owned()));
+    let expr = ScalarExpr::Equal(property("a", "age"), parameter("age"));
+    assert_eq!(
+        condition(&expr, "a", &columns, &params).as_deref(),
+        Some("`age` = 30")
+    );
+    let expr = ScalarExpr::Equal(property("a", "name"), parameter("name"));
+    assert_eq!(condition(&expr, "a", &columns, &params), None);
+    let expr = ScalarExpr::Equal(property("a", "age"), parameter("limit"));
+    assert_eq!(condition(&expr, "a", &columns, &params), None);
+}
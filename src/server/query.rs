@@ -7,6 +7,8 @@ use serde::{Deserialize, Serialize};
 #[derive(Deserialize)]
 pub struct Query {
     query: String,
+    #[serde(default)]
+    params: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -36,6 +38,28 @@ impl From<types::Value> for serde_json::Value {
     }
 }
 
+impl From<serde_json::Value> for types::Value {
+    fn from(value: serde_json::Value) -> Self {
+        use types::Value;
+        match value {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(v) => Value::Boolean(v),
+            serde_json::Value::Number(v) => match v.as_i64() {
+                Some(v) => Value::Int(v),
+                None => Value::Double(v.as_f64().unwrap_or(f64::NAN)),
+            },
+            serde_json::Value::String(v) => Value::String(v),
+            serde_json::Value::Array(v) => Value::Array(types::Array {
+                elements: v.into_iter().map(|v| Value::from(v)).collect(),
+            }),
+            serde_json::Value::Object(v) => Value::Object(types::Object {
+                props: v.into_iter().map(|v| (v.0, Value::from(v.1))).collect(),
+                entity: false,
+            }),
+        }
+    }
+}
+
 impl From<types::Object> for serde_json::Map<String, serde_json::Value> {
     fn from(v: types::Object) -> Self {
         v.props
@@ -45,11 +69,18 @@ impl From<types::Object> for serde_json::Map<String, serde_json::Value> {
     }
 }
 
-fn run_query(q: String) -> Result<QueryResult, Error> {
+fn run_query(
+    q: String,
+    params: serde_json::Map<String, serde_json::Value>,
+) -> Result<QueryResult, Error> {
     let planner = Planner {};
     let parser = Parser::new();
     let ast = parser.parse(q.as_str())?;
-    let mut exec = planner.plan(&ast)?;
+    let params = params
+        .into_iter()
+        .map(|v| (v.0, types::Value::from(v.1)))
+        .collect();
+    let mut exec = planner.plan_with_params(&ast, params)?;
 
     let mut query_result = QueryResult { docs: Vec::new() };
 
@@ -67,7 +98,7 @@ fn run_query(q: String) -> Result<QueryResult, Error> {
 
 #[post("/query")]
 pub async fn query(query: web::Json<Query>) -> impl Responder {
-    match run_query(query.query.to_owned()) {
+    match run_query(query.query.to_owned(), query.params.to_owned()) {
         Ok(query_result) => HttpResponse::Ok()
             .content_type("application/json")
             .body(serde_json::to_string(&query_result).unwrap_or(String::from("{}"))),
@@ -77,12 +108,15 @@ pub async fn query(query: web::Json<Query>) -> impl Responder {
 
 #[test]
 fn test_run_query() {
-    let result = run_query(String::from(
-        r#"
+    let result = run_query(
+        String::from(
+            r#"
     MATCH (n:Person)-[r:knows]->(n1:Person)
     RETURN n, r, n1
     "#,
-    ))
+        ),
+        serde_json::Map::new(),
+    )
     .unwrap();
 
     // println!("{}", serde_json::to_string(&result).unwrap());
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{cmp::Ordering, collections::HashMap};
 
 use mysql::{
     prelude::{ConvIr, FromValue},
@@ -29,6 +29,92 @@ pub enum Value {
     Array(Array),
 }
 
+impl Value {
+    // Total order of values used for sorting. Values of different types are
+    // ordered as map < list < string < boolean < number < null.
+    pub fn order(&self, other: &Value) -> Ordering {
+        fn rank(v: &Value) -> u8 {
+            match v {
+                Value::Object(_) => 0,
+                Value::Array(_) => 1,
+                Value::String(_) => 2,
+                Value::Boolean(_) => 3,
+                Value::Int(_) | Value::Double(_) => 4,
+                Value::Null => 5,
+            }
+        }
+
+        match (self, other) {
+            (Value::Int(l), Value::Int(r)) => l.cmp(r),
+            (Value::Int(l), Value::Double(r)) => {
+                (*l as f64).partial_cmp(r).unwrap_or(Ordering::Equal)
+            }
+            (Value::Double(l), Value::Int(r)) => {
+                l.partial_cmp(&(*r as f64)).unwrap_or(Ordering::Equal)
+            }
+            (Value::Double(l), Value::Double(r)) => l.partial_cmp(r).unwrap_or(Ordering::Equal),
+            (Value::String(l), Value::String(r)) => l.cmp(r),
+            (Value::Boolean(l), Value::Boolean(r)) => l.cmp(r),
+            (Value::Array(l), Value::Array(r)) => {
+                for (l, r) in l.elements.iter().zip(r.elements.iter()) {
+                    match l.order(r) {
+                        Ordering::Equal => {}
+                        ord @ _ => return ord,
+                    }
+                }
+                l.elements.len().cmp(&r.elements.len())
+            }
+            // Entities are ordered by their ids
+            (Value::Object(l), Value::Object(r)) => match (l.get("id"), r.get("id")) {
+                (Some(l), Some(r)) => l.order(r),
+                _ => Ordering::Equal,
+            },
+            (l, r) => rank(l).cmp(&rank(r)),
+        }
+    }
+
+    // Key used to group and deduplicate values, equivalent values have the
+    // same key. Integral numbers have the same key regardless of their types.
+    pub fn group_key(&self) -> String {
+        let mut key = String::new();
+        self.write_group_key(&mut key);
+        key
+    }
+
+    fn write_group_key(&self, key: &mut String) {
+        match self {
+            Value::Null => key.push('n'),
+            Value::Boolean(v) => key.push(if *v { 't' } else { 'f' }),
+            Value::Int(v) => key.push_str(&format!("i{}", v)),
+            Value::Double(v) if v.fract() == 0.0 && v.abs() < i64::MAX as f64 => {
+                key.push_str(&format!("i{}", *v as i64))
+            }
+            Value::Double(v) => key.push_str(&format!("d{}", v)),
+            // Strings are prefixed with length to avoid ambiguity
+            Value::String(v) => key.push_str(&format!("s{}:{}", v.len(), v)),
+            Value::Array(v) => {
+                key.push('[');
+                for e in v.elements.iter() {
+                    e.write_group_key(key);
+                    key.push(',');
+                }
+                key.push(']');
+            }
+            Value::Object(v) => {
+                let mut props: Vec<_> = v.props.iter().collect();
+                props.sort_by(|l, r| l.0.cmp(r.0));
+                key.push('{');
+                for (k, v) in props.into_iter() {
+                    key.push_str(&format!("{}:{}", k.len(), k));
+                    v.write_group_key(key);
+                    key.push(',');
+                }
+                key.push('}');
+            }
+        }
+    }
+}
+
 impl FromValue for Value {
     type Intermediate = Value;
 
@@ -81,15 +167,41 @@ impl From<V> for Value {
     }
 }
 
+impl From<Value> for V {
+    fn from(value: Value) -> V {
+        match value {
+            Value::Int(v) => V::Int(v),
+            Value::Double(v) => V::Double(v),
+            Value::String(v) => V::Bytes(v.into_bytes()),
+            Value::Boolean(v) => V::Int(v as i64),
+            Value::Null => V::NULL,
+            // Nested values are stored as JSON documents
+            v @ Value::Object(_) | v @ Value::Array(_) => {
+                V::Bytes(serde_json::Value::from(v).to_string().into_bytes())
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Object {
     pub props: HashMap<String, Value>,
+    // Whether it's a node or relationship stored in TiDB rather than a map
+    pub entity: bool,
 }
 
 impl Object {
     pub fn new() -> Object {
         Object {
             props: HashMap::new(),
+            entity: false,
+        }
+    }
+
+    pub fn new_entity() -> Object {
+        Object {
+            props: HashMap::new(),
+            entity: true,
         }
     }
 
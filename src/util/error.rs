@@ -26,6 +26,8 @@ impl std::error::Error for Error {}
 #[derive(Debug)]
 pub enum ErrorKind {
     Parse,
+    Semantic,
+    Type,
     Internal,
     Mysql,
     Unknown,
@@ -35,6 +37,8 @@ impl fmt::Display for ErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ErrorKind::Parse => write!(f, "ParseError"),
+            ErrorKind::Semantic => write!(f, "SemanticError"),
+            ErrorKind::Type => write!(f, "TypeError"),
             ErrorKind::Internal => write!(f, "InternalError"),
             ErrorKind::Mysql => write!(f, "MysqlError"),
             ErrorKind::Unknown => write!(f, "UnknownError"),